@@ -6,15 +6,14 @@ use clap::Parser;
 use tabled::builder::Builder;
 use tabled::settings::Style;
 
-const AUTH_NAME: &str = "auth.db";
-
 fn main() {
-    let manager = AuthManager::new(AUTH_NAME);
     let args = CliArg::parse();
+    let manager = AuthManager::new(args.db.as_str());
     match args.command {
-        CliSubCmd::CreateKey { desc } => {
+        CliSubCmd::CreateKey { desc, scope } => {
             println!("Description: {desc:?}");
-            let key = manager.generate_api_key(desc);
+            println!("Scope: {}", scope.as_str());
+            let key = manager.generate_api_key_with_scope(desc, scope);
             println!("✅ Generated API Key: {key}");
         }
         CliSubCmd::EditDescription { prefix, desc } => {
@@ -41,20 +40,58 @@ fn main() {
                 AuthCheckResult::Valid => {
                     println!("✅ The key is valid!");
                 }
-                AuthCheckResult::NoPrefixOrTokenFound => {
-                    println!("❌ The prefix or token is not found.");
+                AuthCheckResult::NoPrefix => {
+                    println!("❌ The prefix is not found.");
+                }
+                AuthCheckResult::WrongToken => {
+                    println!("❌ The prefix is found, but the token is wrong.");
                 }
                 AuthCheckResult::ExpiredKey => {
                     println!("❗ The key is found, but is expired.");
                 }
+                AuthCheckResult::InsufficientScope => {
+                    println!("❗ The key is valid, but is not allowed to make this request.");
+                }
+            }
+        }
+        CliSubCmd::Prune => {
+            let num_removed = manager.purge_expired();
+            println!("✅ Removed {num_removed} expired API Key(s).");
+        }
+        CliSubCmd::ShowAll {
+            show_tokens,
+            json: true,
+        } => {
+            let mut entries = serde_json::to_value(manager.get_all_entries()).unwrap();
+            if !show_tokens.unwrap_or(false) {
+                for entry in entries.as_array_mut().unwrap() {
+                    entry.as_object_mut().unwrap().remove("token");
+                }
             }
+
+            println!("{}", serde_json::to_string_pretty(&entries).unwrap());
         }
-        CliSubCmd::ShowAll { show_tokens } => {
+        CliSubCmd::ShowAll { show_tokens, .. } => {
             let mut table_builder = Builder::new();
             if show_tokens.unwrap_or(false) {
-                table_builder.push_record(["Prefix", "Token", "Created", "Expired", "Description"]);
+                table_builder.push_record([
+                    "Prefix",
+                    "Token (Hashed)",
+                    "Created",
+                    "Expired",
+                    "Description",
+                    "Scope",
+                    "Uses",
+                ]);
             } else {
-                table_builder.push_record(["Prefix", "Created", "Expired", "Description"]);
+                table_builder.push_record([
+                    "Prefix",
+                    "Created",
+                    "Expired",
+                    "Description",
+                    "Scope",
+                    "Uses",
+                ]);
             }
 
             let entries = manager.get_all_entries();
@@ -69,6 +106,8 @@ fn main() {
                     v.push(entry.created_at.to_string());
                     v.push(entry.expires_at.to_string());
                     v.push(entry.description.unwrap_or("N/A".into()));
+                    v.push(entry.scope.as_str().to_owned());
+                    v.push(entry.use_count.to_string());
                     table_builder.push_record(v);
                 }
 
@@ -1,9 +1,14 @@
+use basicauth::Scope;
 use clap::{Parser, Subcommand};
 
 #[derive(Parser)]
 #[command(author, version, about)]
 #[command(propagate_version = true)]
 pub struct CliArg {
+    /// The path to the database holding the API keys. Use `:memory:` for a temporary,
+    /// in-memory database.
+    #[clap(long, global = true, default_value = "auth.db")]
+    pub db: String,
     #[command(subcommand)]
     pub command: CliSubCmd,
 }
@@ -16,6 +21,9 @@ pub enum CliSubCmd {
         /// A description for the key, if any.
         #[clap(name = "desc", short, long)]
         desc: Option<String>,
+        /// What the key is allowed to do, either `read` or `write`.
+        #[clap(name = "scope", short, long, default_value = "write")]
+        scope: Scope,
     },
     /// Edits the description of an existing API key.
     #[clap(name = "editDesc")]
@@ -44,11 +52,17 @@ pub enum CliSubCmd {
         #[clap(name = "token", short, long)]
         token: String,
     },
+    /// Deletes all API keys that have expired.
+    #[clap(name = "prune")]
+    Prune,
     /// Shows all current API keys.
     #[clap(name = "showAll")]
     ShowAll {
-        /// Whether the tokens should be shown.
+        /// Whether the hashed tokens should be shown.
         #[clap(name = "showToken", short, long)]
         show_tokens: Option<bool>,
+        /// Whether the keys should be printed as JSON instead of as a table.
+        #[clap(long)]
+        json: bool,
     },
 }
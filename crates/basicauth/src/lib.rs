@@ -1,7 +1,12 @@
 use chrono::{DateTime, Duration, Utc};
-use rusqlite::{params, Connection};
+use r2d2::Pool;
+use r2d2_sqlite::SqliteConnectionManager;
+use rusqlite::params;
+use serde::Serialize;
+use sha2::{Digest, Sha256};
 use std::borrow::Cow;
-use std::sync::Mutex;
+use std::str::FromStr;
+use subtle::ConstantTimeEq;
 use uuid::Uuid;
 
 const EXP_AT_COLUMN: &str = "expires_at";
@@ -9,11 +14,25 @@ const PREFIX_COLUMN: &str = "prefix";
 const TOKEN_COLUMN: &str = "token";
 const CREATED_AT_COLUMN: &str = "created_at";
 const DESCRIPTION_COLUMN: &str = "description";
+const USE_COUNT_COLUMN: &str = "use_count";
+const SCOPE_COLUMN: &str = "scope";
+
+/// The prefix of every hashed token stored in the database. Tokens without this prefix
+/// were stored in plaintext by an older version.
+const HASH_PREFIX: &str = "sha256$";
+
+/// The name that can be used in place of a file name to use an in-memory database.
+const MEMORY_DB_NAME: &str = ":memory:";
+/// The number of days that a key is valid for, unless otherwise specified.
+const DEFAULT_VALID_DAYS: i64 = 365;
+/// How long a connection should wait for the database to be unlocked before giving up.
+const BUSY_TIMEOUT: std::time::Duration = std::time::Duration::from_secs(5);
 
 /// A structure representing a simple authentication manager.
 pub struct AuthManager {
-    /// The SQLite database that is responsible for holding the database information.
-    pub db: Mutex<Connection>,
+    /// The pool of connections to the SQLite database that is responsible for holding the
+    /// database information.
+    pub db: Pool<SqliteConnectionManager>,
 }
 
 impl AuthManager {
@@ -21,21 +40,76 @@ impl AuthManager {
     /// containing API keys _if_ the table doesn't exist.
     ///
     /// # Parameters
-    /// - `db_name`: The name of the database file.
+    /// - `db_name`: The name of the database file, or `:memory:` for an in-memory
+    ///   database.
     ///
     /// # Returns
     /// The authentication manager.
     pub fn new(db_name: &str) -> Self {
-        let conn = Connection::open(db_name).unwrap();
+        let db = if db_name == MEMORY_DB_NAME {
+            // Every connection to an in-memory database gets its own database, so there can
+            // only be one connection.
+            Pool::builder()
+                .max_size(1)
+                .build(SqliteConnectionManager::memory())
+                .unwrap()
+        } else {
+            let manager = SqliteConnectionManager::file(db_name).with_init(|conn| {
+                conn.busy_timeout(BUSY_TIMEOUT)?;
+                // Allows reads to happen while another connection is writing.
+                conn.pragma_update_and_check(None, "journal_mode", "WAL", |_| Ok(()))
+            });
+            Pool::new(manager).unwrap()
+        };
+
+        let conn = db.get().unwrap();
         conn.execute(include_str!("../../../sql/init_table.sql"), ())
             .unwrap();
+        // Tables created by older versions don't have a use count. If the column already
+        // exists, this fails, which is fine.
+        let _ = conn.execute(include_str!("../../../sql/add_use_count.sql"), ());
+        // The same goes for scopes. Keys created before scopes existed keep full access.
+        let _ = conn.execute(include_str!("../../../sql/add_scope.sql"), ());
+        drop(conn);
 
-        Self {
-            db: Mutex::new(conn),
-        }
+        let manager = Self { db };
+        manager.migrate_plaintext_tokens();
+        manager
     }
 
-    /// Generates an API key that can be used to make requests to this server.
+    /// Hashes any tokens that are stored in plaintext. Tokens were stored in plaintext by
+    /// older versions, so this is run automatically when the manager is created.
+    ///
+    /// # Returns
+    /// The number of tokens that were hashed.
+    pub fn migrate_plaintext_tokens(&self) -> usize {
+        let conn = self.db.get().unwrap();
+        let mut stmt = conn
+            .prepare(include_str!("../../../sql/get_plaintext_entries.sql"))
+            .unwrap();
+        let tokens: Vec<String> = stmt
+            .query_map((), |row| row.get::<_, String>(TOKEN_COLUMN))
+            .unwrap()
+            .map(|data| data.unwrap())
+            .collect();
+
+        let mut stmt = conn
+            .prepare(include_str!("../../../sql/edit_token_by_token.sql"))
+            .unwrap();
+        tokens
+            .iter()
+            .filter(|token| {
+                let salt = Uuid::new_v4().simple().to_string();
+                matches!(
+                    stmt.execute(params![hash_token(token, salt.as_str()), token]),
+                    Ok(n) if n > 0
+                )
+            })
+            .count()
+    }
+
+    /// Generates an API key that can be used to make requests to this server. This key
+    /// will be valid for 365 days.
     ///
     /// # Parameters
     /// - `desc`: A description for this API key, if any.
@@ -43,23 +117,140 @@ impl AuthManager {
     /// # Returns
     /// A new API key.
     pub fn generate_api_key<'a>(&self, desc: Option<impl Into<Cow<'a, str>>>) -> String {
+        self.generate_api_key_with_expiry(desc, Duration::days(DEFAULT_VALID_DAYS))
+    }
+
+    /// Generates an API key that can be used to make requests to this server, valid for
+    /// the specified duration.
+    ///
+    /// # Parameters
+    /// - `desc`: A description for this API key, if any.
+    /// - `valid_for`: How long this API key should be valid for.
+    ///
+    /// # Returns
+    /// A new API key.
+    pub fn generate_api_key_with_expiry<'a>(
+        &self,
+        desc: Option<impl Into<Cow<'a, str>>>,
+        valid_for: Duration,
+    ) -> String {
+        self.generate_api_key_with_options(desc, valid_for, Scope::Write)
+    }
+
+    /// Generates an API key with the given scope. This key will be valid for 365 days.
+    ///
+    /// # Parameters
+    /// - `desc`: A description for this API key, if any.
+    /// - `scope`: What this API key is allowed to do.
+    ///
+    /// # Returns
+    /// A new API key.
+    pub fn generate_api_key_with_scope<'a>(
+        &self,
+        desc: Option<impl Into<Cow<'a, str>>>,
+        scope: Scope,
+    ) -> String {
+        self.generate_api_key_with_options(desc, Duration::days(DEFAULT_VALID_DAYS), scope)
+    }
+
+    /// Generates an API key that can be used to make requests to this server.
+    ///
+    /// # Parameters
+    /// - `desc`: A description for this API key, if any.
+    /// - `valid_for`: How long this API key should be valid for.
+    /// - `scope`: What this API key is allowed to do.
+    ///
+    /// # Returns
+    /// A new API key.
+    fn generate_api_key_with_options<'a>(
+        &self,
+        desc: Option<impl Into<Cow<'a, str>>>,
+        valid_for: Duration,
+        scope: Scope,
+    ) -> String {
         let prefix = Uuid::new_v4().to_string();
         let key = Uuid::new_v4().to_string();
-        let conn = self.db.lock().unwrap();
+        let conn = self.db.get().unwrap();
         let description = desc.map(|s| s.into());
 
+        // Only the hash of the key is stored, so the key itself is only known by the caller.
+        let salt = Uuid::new_v4().simple().to_string();
+        let hashed_key = hash_token(key.as_str(), salt.as_str());
+
         let date_time = Utc::now();
-        let expiration_time = date_time + Duration::days(365);
+        let expiration_time = date_time + valid_for;
         conn.execute(
             include_str!("../../../sql/insert_table.sql"),
-            params![&prefix, &key, date_time, expiration_time, description],
+            params![
+                &prefix,
+                &hashed_key,
+                date_time,
+                expiration_time,
+                description,
+                scope.as_str()
+            ],
         )
         .unwrap();
 
         format!("{prefix}#{key}")
     }
 
-    /// Checks that the prefix and key that's given is valid.
+    /// Generates an API key for each of the given descriptions. All keys are inserted in a
+    /// single transaction, so either every key is added or none of them are.
+    ///
+    /// # Parameters
+    /// - `descriptions`: The descriptions of the keys, one for each key to generate.
+    ///
+    /// # Returns
+    /// The API keys, in the same order as the descriptions. If any key could not be
+    /// inserted, none of the keys are added and this will be empty.
+    pub fn generate_api_keys(&self, descriptions: &[Option<&str>]) -> Vec<String> {
+        let mut conn = self.db.get().unwrap();
+        let Ok(tx) = conn.transaction() else {
+            return vec![];
+        };
+
+        let mut keys = Vec::with_capacity(descriptions.len());
+        {
+            let Ok(mut stmt) = tx.prepare(include_str!("../../../sql/insert_table.sql")) else {
+                return vec![];
+            };
+
+            let date_time = Utc::now();
+            let expiration_time = date_time + Duration::days(DEFAULT_VALID_DAYS);
+            for description in descriptions {
+                let prefix = Uuid::new_v4().to_string();
+                let key = Uuid::new_v4().to_string();
+                let salt = Uuid::new_v4().simple().to_string();
+                let hashed_key = hash_token(key.as_str(), salt.as_str());
+
+                // Dropping the transaction without committing it rolls back every insert.
+                if stmt
+                    .execute(params![
+                        &prefix,
+                        &hashed_key,
+                        date_time,
+                        expiration_time,
+                        description,
+                        Scope::Write.as_str()
+                    ])
+                    .is_err()
+                {
+                    return vec![];
+                }
+
+                keys.push(format!("{prefix}#{key}"));
+            }
+        }
+
+        match tx.commit() {
+            Ok(_) => keys,
+            Err(_) => vec![],
+        }
+    }
+
+    /// Checks that the prefix and key that's given is valid. Keys of any scope are
+    /// accepted.
     ///
     /// # Parameters
     /// - `prefix`: The prefix, used to identify the user.
@@ -68,27 +259,71 @@ impl AuthManager {
     /// # Returns
     /// The check results.
     pub fn check_key(&self, prefix: &str, token: &str) -> AuthCheckResult {
-        let conn = self.db.lock().unwrap();
+        self.check_key_with_scope(prefix, token, Scope::Read)
+    }
+
+    /// Checks that the prefix and key that's given is valid, and that the key is allowed
+    /// to do what's required of it.
+    ///
+    /// # Parameters
+    /// - `prefix`: The prefix, used to identify the user.
+    /// - `token`: The token associated with this key.
+    /// - `required`: The scope that the key needs to have.
+    ///
+    /// # Returns
+    /// The check results.
+    pub fn check_key_with_scope(
+        &self,
+        prefix: &str,
+        token: &str,
+        required: Scope,
+    ) -> AuthCheckResult {
+        let conn = self.db.get().unwrap();
         let mut stmt = conn
-            .prepare(include_str!("../../../sql/get_by_prefix.sql"))
+            .prepare(include_str!("../../../sql/get_entry_by_prefix.sql"))
             .unwrap();
-        let mut res: Vec<_> = stmt
-            .query_map(params![prefix, token], |row| {
-                Ok(row.get::<_, DateTime<Utc>>(EXP_AT_COLUMN).unwrap())
+        let entry = stmt
+            .query_row(params![prefix], |row| {
+                Ok((
+                    row.get::<_, String>(TOKEN_COLUMN)?,
+                    row.get::<_, DateTime<Utc>>(EXP_AT_COLUMN)?,
+                    row.get::<_, String>(SCOPE_COLUMN)?,
+                ))
             })
-            .unwrap()
-            .collect();
+            .ok();
+
+        let Some((hashed_token, expiration_time, scope)) = entry else {
+            return AuthCheckResult::NoPrefix;
+        };
 
-        if res.is_empty() {
-            return AuthCheckResult::NoPrefixOrTokenFound;
+        let salt = hashed_token
+            .strip_prefix(HASH_PREFIX)
+            .and_then(|s| s.split_once('$'))
+            .map(|(salt, _)| salt)
+            .unwrap_or_default();
+        // Compare in constant time so that how long the check takes doesn't reveal how much
+        // of the token was correct.
+        let is_match: bool = hash_token(token, salt)
+            .as_bytes()
+            .ct_eq(hashed_token.as_bytes())
+            .into();
+        if !is_match {
+            return AuthCheckResult::WrongToken;
         }
 
-        let elem = res.pop().unwrap();
-        let expiration_time = elem.unwrap();
         if expiration_time.timestamp() - Utc::now().timestamp() < 0 {
             return AuthCheckResult::ExpiredKey;
         }
 
+        if !Scope::from_str(scope.as_str()).is_ok_and(|scope| scope.allows(required)) {
+            return AuthCheckResult::InsufficientScope;
+        }
+
+        conn.execute(
+            include_str!("../../../sql/increment_use_count.sql"),
+            params![prefix],
+        )
+        .unwrap();
         AuthCheckResult::Valid
     }
 
@@ -101,7 +336,7 @@ impl AuthManager {
     /// # Returns
     /// `true` if deletion was successful, and `false` otherwise.
     pub fn delete_by_prefix(&self, prefix: &str) -> bool {
-        let conn = self.db.lock().unwrap();
+        let conn = self.db.get().unwrap();
         let mut stmt = conn
             .prepare(include_str!("../../../sql/delete_by_prefix.sql"))
             .unwrap();
@@ -109,6 +344,19 @@ impl AuthManager {
         matches!(stmt.execute(params![prefix]), Ok(n) if n > 0)
     }
 
+    /// Deletes all API keys that have expired.
+    ///
+    /// # Returns
+    /// The number of keys that were deleted.
+    pub fn purge_expired(&self) -> usize {
+        let conn = self.db.get().unwrap();
+        let mut stmt = conn
+            .prepare(include_str!("../../../sql/delete_expired.sql"))
+            .unwrap();
+
+        stmt.execute(params![Utc::now()]).unwrap_or(0)
+    }
+
     /// Edits the description associated with a prefix.
     ///
     /// # Parameters
@@ -122,7 +370,7 @@ impl AuthManager {
         prefix: &str,
         desc: Option<impl Into<Cow<'a, str>>>,
     ) -> bool {
-        let conn = self.db.lock().unwrap();
+        let conn = self.db.get().unwrap();
         let mut stmt = conn
             .prepare(include_str!("../../../sql/edit_desc_by_prefix.sql"))
             .unwrap();
@@ -130,12 +378,44 @@ impl AuthManager {
         matches!(stmt.execute(params![description, prefix]), Ok(n) if n > 0)
     }
 
+    /// Extends the expiration time of the key associated with a prefix. If the key has
+    /// already expired, it's extended from the current time instead, so that renewing an
+    /// expired key makes it valid again.
+    ///
+    /// # Parameters
+    /// - `prefix`: The prefix to renew.
+    /// - `extend_by`: How long the key's validity should be extended by.
+    ///
+    /// # Returns
+    /// `true` if renewal was successful, and `false` otherwise.
+    pub fn renew_key(&self, prefix: &str, extend_by: Duration) -> bool {
+        let conn = self.db.get().unwrap();
+        let mut stmt = conn
+            .prepare(include_str!("../../../sql/get_entry_by_prefix.sql"))
+            .unwrap();
+        let expiration_time = stmt
+            .query_row(params![prefix], |row| {
+                row.get::<_, DateTime<Utc>>(EXP_AT_COLUMN)
+            })
+            .ok();
+
+        let Some(expiration_time) = expiration_time else {
+            return false;
+        };
+
+        let new_expiration_time = expiration_time.max(Utc::now()) + extend_by;
+        let mut stmt = conn
+            .prepare(include_str!("../../../sql/edit_exp_by_prefix.sql"))
+            .unwrap();
+        matches!(stmt.execute(params![new_expiration_time, prefix]), Ok(n) if n > 0)
+    }
+
     /// Gets all prefixes currently in this database.
     ///
     /// # Returns
     /// A list of all prefixes.
     pub fn get_all_prefixes(&self) -> Vec<String> {
-        let conn = self.db.lock().unwrap();
+        let conn = self.db.get().unwrap();
         let mut stmt = conn
             .prepare(include_str!("../../../sql/get_all_entries.sql"))
             .unwrap();
@@ -151,42 +431,129 @@ impl AuthManager {
     /// # Returns
     /// A list of all entries.
     pub fn get_all_entries(&self) -> Vec<ApiKeyEntry> {
-        let conn = self.db.lock().unwrap();
+        let conn = self.db.get().unwrap();
         let mut stmt = conn
             .prepare(include_str!("../../../sql/get_all_entries.sql"))
             .unwrap();
 
-        stmt.query_map((), |row| {
-            Ok(ApiKeyEntry {
-                prefix: row.get::<_, String>(PREFIX_COLUMN).unwrap(),
-                token: row.get::<_, String>(TOKEN_COLUMN).unwrap(),
-                created_at: row.get::<_, DateTime<Utc>>(CREATED_AT_COLUMN).unwrap(),
-                expires_at: row.get::<_, DateTime<Utc>>(EXP_AT_COLUMN).unwrap(),
-                description: row.get::<_, Option<String>>(DESCRIPTION_COLUMN).unwrap(),
-            })
-        })
-        .unwrap()
-        .map(|data| data.unwrap())
-        .collect()
+        stmt.query_map((), ApiKeyEntry::from_row)
+            .unwrap()
+            .map(|data| data.unwrap())
+            .collect()
+    }
+
+    /// Gets the entry associated with a prefix.
+    ///
+    /// # Parameters
+    /// - `prefix`: The prefix.
+    ///
+    /// # Returns
+    /// The entry, if the prefix exists.
+    pub fn get_entry_by_prefix(&self, prefix: &str) -> Option<ApiKeyEntry> {
+        let conn = self.db.get().unwrap();
+        let mut stmt = conn
+            .prepare(include_str!("../../../sql/get_entry_by_prefix.sql"))
+            .unwrap();
+
+        stmt.query_row(params![prefix], ApiKeyEntry::from_row).ok()
     }
 }
 
+/// Hashes the given token with the given salt.
+///
+/// # Parameters
+/// - `token`: The token to hash.
+/// - `salt`: The salt.
+///
+/// # Returns
+/// The hashed token, in the form `sha256$<salt>$<hash>`.
+fn hash_token(token: &str, salt: &str) -> String {
+    let mut hasher = Sha256::new();
+    hasher.update(salt.as_bytes());
+    hasher.update(token.as_bytes());
+    let hash = hasher
+        .finalize()
+        .iter()
+        .map(|b| format!("{b:02x}"))
+        .collect::<String>();
+
+    format!("{HASH_PREFIX}{salt}${hash}")
+}
+
 /// An enum representing the result of checking for the prefix and key.
 #[derive(Eq, PartialEq, Debug)]
 pub enum AuthCheckResult {
     /// Whether the prefix exists and the associated key is valid.
     Valid,
-    /// Whether the prefix does not exist, or the key is not found.
-    NoPrefixOrTokenFound,
+    /// Whether the prefix does not exist.
+    NoPrefix,
+    /// Whether the prefix exists, but the token doesn't match the one for that prefix.
+    WrongToken,
     /// Whether the key has expired.
     ExpiredKey,
+    /// Whether the key is valid, but isn't allowed to do what was requested.
+    InsufficientScope,
+}
+
+/// What an API key is allowed to do.
+#[derive(Debug, Copy, Clone, PartialEq, Eq, Serialize)]
+#[serde(rename_all = "lowercase")]
+pub enum Scope {
+    /// The key can only be used for requests that don't change anything.
+    Read,
+    /// The key can be used for any request.
+    Write,
+}
+
+impl Scope {
+    /// Gets the name of this scope, as stored in the database.
+    ///
+    /// # Returns
+    /// The name of this scope.
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            Scope::Read => "read",
+            Scope::Write => "write",
+        }
+    }
+
+    /// Checks whether this scope grants the required scope.
+    ///
+    /// # Parameters
+    /// - `required`: The scope that's required.
+    ///
+    /// # Returns
+    /// Whether this scope grants the required scope.
+    pub fn allows(&self, required: Scope) -> bool {
+        match required {
+            Scope::Read => true,
+            Scope::Write => *self == Scope::Write,
+        }
+    }
+}
+
+impl FromStr for Scope {
+    type Err = String;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        match s {
+            "read" => Ok(Scope::Read),
+            "write" => Ok(Scope::Write),
+            _ => Err(format!(
+                "'{s}' is not a valid scope (expected 'read' or 'write')"
+            )),
+        }
+    }
 }
 
 /// Represents an entry in the database.
+#[derive(Serialize)]
+#[serde(rename_all = "camelCase")]
 pub struct ApiKeyEntry {
     /// The prefix for this API key.
     pub prefix: String,
-    /// The token for this API key.
+    /// The hashed token for this API key, in the form `sha256$<salt>$<hash>`. The token
+    /// itself is not stored, and so it can't be recovered.
     pub token: String,
     /// When this API key was created.
     pub created_at: DateTime<Utc>,
@@ -194,4 +561,30 @@ pub struct ApiKeyEntry {
     pub expires_at: DateTime<Utc>,
     /// Any description for this key.
     pub description: Option<String>,
+    /// The number of times this key has been successfully used.
+    pub use_count: u64,
+    /// What this key is allowed to do.
+    pub scope: Scope,
+}
+
+impl ApiKeyEntry {
+    /// Creates an entry from a row in the database.
+    ///
+    /// # Parameters
+    /// - `row`: The row.
+    ///
+    /// # Returns
+    /// The entry.
+    fn from_row(row: &rusqlite::Row) -> rusqlite::Result<Self> {
+        Ok(Self {
+            prefix: row.get::<_, String>(PREFIX_COLUMN)?,
+            token: row.get::<_, String>(TOKEN_COLUMN)?,
+            created_at: row.get::<_, DateTime<Utc>>(CREATED_AT_COLUMN)?,
+            expires_at: row.get::<_, DateTime<Utc>>(EXP_AT_COLUMN)?,
+            description: row.get::<_, Option<String>>(DESCRIPTION_COLUMN)?,
+            use_count: row.get::<_, u64>(USE_COUNT_COLUMN)?,
+            scope: Scope::from_str(row.get::<_, String>(SCOPE_COLUMN)?.as_str())
+                .unwrap_or(Scope::Read),
+        })
+    }
 }
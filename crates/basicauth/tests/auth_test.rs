@@ -1,4 +1,5 @@
-use basicauth::{AuthCheckResult, AuthManager};
+use basicauth::{AuthCheckResult, AuthManager, Scope};
+use chrono::Duration;
 
 const MEMORY_DB: &str = ":memory:";
 #[test]
@@ -13,13 +14,17 @@ fn test_add_keys_check() {
     assert_eq!(AuthCheckResult::Valid, manager.check_key(prefix1, token1));
     assert_eq!(AuthCheckResult::Valid, manager.check_key(prefix2, token2));
     assert_eq!(
-        AuthCheckResult::NoPrefixOrTokenFound,
+        AuthCheckResult::WrongToken,
         manager.check_key(prefix2, token1)
     );
     assert_eq!(
-        AuthCheckResult::NoPrefixOrTokenFound,
+        AuthCheckResult::WrongToken,
         manager.check_key(prefix1, token2)
     );
+    assert_eq!(
+        AuthCheckResult::NoPrefix,
+        manager.check_key("not a prefix", token1)
+    );
 }
 
 #[test]
@@ -76,7 +81,7 @@ fn test_delete_key() {
     assert_eq!(3, all_prefixes.len());
     assert!(manager.delete_by_prefix(prefix2));
     assert_eq!(
-        AuthCheckResult::NoPrefixOrTokenFound,
+        AuthCheckResult::NoPrefix,
         manager.check_key(prefix2, token2)
     );
 
@@ -84,3 +89,299 @@ fn test_delete_key() {
     assert_eq!(2, all_prefixes2.len());
     assert!(!manager.delete_by_prefix(prefix2));
 }
+
+#[test]
+fn test_expired_key() {
+    let manager = AuthManager::new(MEMORY_DB);
+    let key = manager.generate_api_key_with_expiry(Some("this is a test"), Duration::seconds(1));
+    let (prefix, token) = key.split_once('#').unwrap();
+    assert_eq!(AuthCheckResult::Valid, manager.check_key(prefix, token));
+
+    std::thread::sleep(std::time::Duration::from_secs(2));
+    assert_eq!(
+        AuthCheckResult::ExpiredKey,
+        manager.check_key(prefix, token)
+    );
+}
+
+#[test]
+fn test_renew_key() {
+    let manager = AuthManager::new(MEMORY_DB);
+    let key = manager.generate_api_key_with_expiry(Some("this is a test"), Duration::seconds(1));
+    let (prefix, token) = key.split_once('#').unwrap();
+
+    std::thread::sleep(std::time::Duration::from_secs(2));
+    assert_eq!(
+        AuthCheckResult::ExpiredKey,
+        manager.check_key(prefix, token)
+    );
+
+    assert!(manager.renew_key(prefix, Duration::days(1)));
+    assert_eq!(AuthCheckResult::Valid, manager.check_key(prefix, token));
+    assert!(manager.get_all_entries()[0].expires_at > chrono::Utc::now() + Duration::hours(23));
+}
+
+#[test]
+fn test_renew_missing_key() {
+    let manager = AuthManager::new(MEMORY_DB);
+    manager.generate_api_key(Some("this is a test"));
+    assert!(!manager.renew_key("not a prefix", Duration::days(1)));
+}
+
+#[test]
+fn test_tokens_are_hashed() {
+    let manager = AuthManager::new(MEMORY_DB);
+    let key = manager.generate_api_key(Some("this is a test"));
+    let (prefix, token) = key.split_once('#').unwrap();
+
+    let entries = manager.get_all_entries();
+    assert_ne!(token, entries[0].token);
+    assert!(!entries[0].token.contains(token));
+    assert_eq!(AuthCheckResult::Valid, manager.check_key(prefix, token));
+}
+
+#[test]
+fn test_migrate_plaintext_tokens() {
+    let manager = AuthManager::new(MEMORY_DB);
+    manager
+        .db
+        .get()
+        .unwrap()
+        .execute(
+            "INSERT INTO `api_tokens` (prefix, token, created_at, expires_at, description) VALUES (?1, ?2, ?3, ?4, NULL)",
+            rusqlite::params![
+                "old-prefix",
+                "old-token",
+                chrono::Utc::now(),
+                chrono::Utc::now() + Duration::days(1)
+            ],
+        )
+        .unwrap();
+
+    assert_eq!(1, manager.migrate_plaintext_tokens());
+    assert_eq!(0, manager.migrate_plaintext_tokens());
+    assert_ne!("old-token", manager.get_all_entries()[0].token);
+    assert_eq!(
+        AuthCheckResult::Valid,
+        manager.check_key("old-prefix", "old-token")
+    );
+}
+
+#[test]
+fn test_wrong_token_for_prefix() {
+    let manager = AuthManager::new(MEMORY_DB);
+    let key = manager.generate_api_key(Some("this is a test"));
+    let (prefix, token) = key.split_once('#').unwrap();
+
+    // Tokens that share a prefix with the real token, of varying lengths, should all fail.
+    for len in [0, 1, token.len() / 2, token.len() - 1] {
+        assert_eq!(
+            AuthCheckResult::WrongToken,
+            manager.check_key(prefix, &token[..len])
+        );
+    }
+
+    assert_eq!(
+        AuthCheckResult::WrongToken,
+        manager.check_key(prefix, format!("{token}0").as_str())
+    );
+    assert_eq!(AuthCheckResult::Valid, manager.check_key(prefix, token));
+}
+
+/// Computes what a token is compared against the stored hash as. This mirrors how tokens
+/// are hashed when they're stored.
+fn comparison_input(token: &str, salt: &str) -> String {
+    use sha2::{Digest, Sha256};
+
+    let mut hasher = Sha256::new();
+    hasher.update(salt.as_bytes());
+    hasher.update(token.as_bytes());
+    let hash = hasher
+        .finalize()
+        .iter()
+        .map(|b| format!("{b:02x}"))
+        .collect::<String>();
+    format!("sha256${salt}${hash}")
+}
+
+/// Replaces the character at the given index of the token with a different one.
+fn change_char(token: &str, idx: usize) -> String {
+    let replacement = if &token[idx..=idx] == "a" { "b" } else { "a" };
+    format!("{}{replacement}{}", &token[..idx], &token[idx + 1..])
+}
+
+// Measuring how long a check takes would make for a flaky test, so this instead checks that
+// the check can't stop early: however much of a wrong token is correct, what's compared is
+// a hash of the same length as the stored one, which only shares the salt with it.
+#[test]
+fn test_token_comparison_is_full_length() {
+    let manager = AuthManager::new(MEMORY_DB);
+    let key = manager.generate_api_key(Some("this is a test"));
+    let (prefix, token) = key.split_once('#').unwrap();
+    let stored = manager.get_entry_by_prefix(prefix).unwrap().token;
+    let salt = stored
+        .strip_prefix("sha256$")
+        .and_then(|s| s.split_once('$'))
+        .map(|(salt, _)| salt)
+        .unwrap();
+    assert_eq!(stored, comparison_input(token, salt));
+
+    // One token shares no leading characters with the real token, and the other shares all
+    // but the last one.
+    let no_shared = change_char(token, 0);
+    let most_shared = change_char(token, token.len() - 1);
+    let num_shared = |input: &str| {
+        input
+            .bytes()
+            .zip(stored.bytes())
+            .take_while(|(a, b)| a == b)
+            .count()
+    };
+
+    let salt_len = "sha256$".len() + salt.len() + 1;
+    for wrong in [no_shared, most_shared] {
+        assert_eq!(
+            AuthCheckResult::WrongToken,
+            manager.check_key(prefix, wrong.as_str())
+        );
+
+        let input = comparison_input(wrong.as_str(), salt);
+        assert_eq!(stored.len(), input.len());
+        // Any characters of the hash itself that happen to match are a coincidence, so
+        // allow for a few.
+        assert!(num_shared(input.as_str()) < salt_len + 4);
+    }
+}
+
+#[test]
+fn test_use_count() {
+    let manager = AuthManager::new(MEMORY_DB);
+    let key = manager.generate_api_key(Some("this is a test"));
+    let (prefix, token) = key.split_once('#').unwrap();
+    assert_eq!(0, manager.get_entry_by_prefix(prefix).unwrap().use_count);
+
+    for _ in 0..3 {
+        assert_eq!(AuthCheckResult::Valid, manager.check_key(prefix, token));
+    }
+
+    // Failed checks shouldn't count.
+    manager.check_key(prefix, "not the token");
+    assert_eq!(3, manager.get_entry_by_prefix(prefix).unwrap().use_count);
+    assert!(manager.get_entry_by_prefix("not a prefix").is_none());
+}
+
+#[test]
+fn test_concurrent_check_key() {
+    let db_name = std::env::temp_dir().join(format!("auth_test_{}.db", std::process::id()));
+    let db_name = db_name.to_str().unwrap();
+    let manager = std::sync::Arc::new(AuthManager::new(db_name));
+    let key = manager.generate_api_key(Some("this is a test"));
+    let (prefix, token) = key.split_once('#').unwrap();
+
+    let handles = (0..8)
+        .map(|_| {
+            let manager = manager.clone();
+            let prefix = prefix.to_owned();
+            let token = token.to_owned();
+            std::thread::spawn(move || {
+                for _ in 0..10 {
+                    assert_eq!(AuthCheckResult::Valid, manager.check_key(&prefix, &token));
+                    assert_eq!(
+                        AuthCheckResult::WrongToken,
+                        manager.check_key(&prefix, "not the token")
+                    );
+                }
+            })
+        })
+        .collect::<Vec<_>>();
+
+    for handle in handles {
+        handle.join().unwrap();
+    }
+
+    assert_eq!(80, manager.get_entry_by_prefix(prefix).unwrap().use_count);
+    drop(manager);
+    for suffix in ["", "-wal", "-shm"] {
+        let _ = std::fs::remove_file(format!("{db_name}{suffix}"));
+    }
+}
+
+#[test]
+fn test_generate_api_keys() {
+    let manager = AuthManager::new(MEMORY_DB);
+    let descriptions = (0..100).map(|_| Some("student")).collect::<Vec<_>>();
+    let keys = manager.generate_api_keys(&descriptions);
+    assert_eq!(100, keys.len());
+    assert_eq!(100, manager.get_all_prefixes().len());
+
+    for key in keys {
+        let (prefix, token) = key.split_once('#').unwrap();
+        assert_eq!(AuthCheckResult::Valid, manager.check_key(prefix, token));
+    }
+}
+
+#[test]
+fn test_purge_expired() {
+    let manager = AuthManager::new(MEMORY_DB);
+    manager.generate_api_key_with_expiry(Some("expired"), Duration::days(-1));
+    let key = manager.generate_api_key(Some("valid"));
+    let (prefix, token) = key.split_once('#').unwrap();
+
+    assert_eq!(1, manager.purge_expired());
+    assert_eq!(vec![prefix.to_owned()], manager.get_all_prefixes());
+    assert_eq!(AuthCheckResult::Valid, manager.check_key(prefix, token));
+    assert_eq!(0, manager.purge_expired());
+}
+
+#[test]
+fn test_scopes() {
+    let manager = AuthManager::new(MEMORY_DB);
+    let read_key = manager.generate_api_key_with_scope(Some("read"), Scope::Read);
+    let write_key = manager.generate_api_key_with_scope(Some("write"), Scope::Write);
+    let (read_prefix, read_token) = read_key.split_once('#').unwrap();
+    let (write_prefix, write_token) = write_key.split_once('#').unwrap();
+
+    assert_eq!(
+        AuthCheckResult::Valid,
+        manager.check_key_with_scope(read_prefix, read_token, Scope::Read)
+    );
+    assert_eq!(
+        AuthCheckResult::InsufficientScope,
+        manager.check_key_with_scope(read_prefix, read_token, Scope::Write)
+    );
+    assert_eq!(
+        AuthCheckResult::Valid,
+        manager.check_key_with_scope(write_prefix, write_token, Scope::Read)
+    );
+    assert_eq!(
+        AuthCheckResult::Valid,
+        manager.check_key_with_scope(write_prefix, write_token, Scope::Write)
+    );
+
+    assert_eq!(
+        Scope::Read,
+        manager.get_entry_by_prefix(read_prefix).unwrap().scope
+    );
+    assert_eq!(
+        Scope::Write,
+        manager.get_entry_by_prefix(write_prefix).unwrap().scope
+    );
+}
+
+#[test]
+fn test_entry_serialization() {
+    let manager = AuthManager::new(MEMORY_DB);
+    manager.generate_api_key(None::<&str>);
+    let entry = manager.get_all_entries().pop().unwrap();
+    let json = serde_json::to_value(&entry).unwrap();
+
+    assert_eq!(serde_json::Value::Null, json["description"]);
+    assert_eq!("write", json["scope"]);
+    for (key, time) in [
+        ("createdAt", entry.created_at),
+        ("expiresAt", entry.expires_at),
+    ] {
+        let parsed = chrono::DateTime::parse_from_rfc3339(json[key].as_str().unwrap()).unwrap();
+        assert_eq!(time, parsed);
+    }
+}
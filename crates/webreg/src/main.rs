@@ -1,24 +1,68 @@
+use crate::scraper::retention::run_retention;
+use crate::scraper::terms::expand_current_terms;
 use crate::scraper::tracker::run_tracker;
 use crate::server::create_router;
 use crate::types::{ConfigScraper, WrapperState};
 use std::fs;
 use std::net::SocketAddr;
-use std::path::Path;
+use std::path::{Path, PathBuf};
 use std::process::ExitCode;
 use std::str::FromStr;
 use std::sync::Arc;
 use std::time::Duration;
 use tracing::log::{error, info, warn};
+use tracing_subscriber::filter::{LevelFilter, Targets};
+use tracing_subscriber::prelude::*;
+use tracing_subscriber::{fmt, reload};
 
 mod scraper;
 mod server;
 mod types;
 
 const VERSION: &str = env!("CARGO_PKG_VERSION");
+/// The default amount of time, in seconds, to wait for the trackers to stop when shutting
+/// down.
+const DEFAULT_SHUTDOWN_TIMEOUT_SECS: u64 = 30;
+/// The environment variable that selects the format that logs are written in.
+const LOG_FORMAT_VAR: &str = "LOG_FORMAT";
+
+/// The format that logs are written in.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+enum LogFormat {
+    /// Human-readable logs.
+    Pretty,
+    /// One JSON object per line, for log aggregators.
+    Json,
+}
+
+impl LogFormat {
+    /// Selects the log format from the value of the `LOG_FORMAT` environment variable.
+    ///
+    /// # Parameters
+    /// - `value`: The value of the environment variable, if it's set.
+    ///
+    /// # Returns
+    /// `Json` if the value is `json` (ignoring case), and `Pretty` otherwise.
+    fn from_env_value(value: Option<&str>) -> Self {
+        match value {
+            Some(value) if value.trim().eq_ignore_ascii_case("json") => LogFormat::Json,
+            _ => LogFormat::Pretty,
+        }
+    }
+}
 
 #[tokio::main]
 async fn main() -> ExitCode {
-    tracing_subscriber::fmt::init();
+    let (log_filter, log_filter_handle) =
+        reload::Layer::new(Targets::new().with_default(LevelFilter::INFO));
+    let log_format = LogFormat::from_env_value(std::env::var(LOG_FORMAT_VAR).ok().as_deref());
+    tracing_subscriber::registry()
+        .with(log_filter)
+        .with(match log_format {
+            LogFormat::Pretty => fmt::layer().boxed(),
+            LogFormat::Json => fmt::layer().json().boxed(),
+        })
+        .init();
     info!("Started webreg_scraper, version {VERSION}");
     // First, get the configuration file.
     let config_path = match std::env::args().skip(1).last() {
@@ -35,23 +79,58 @@ async fn main() -> ExitCode {
         return ExitCode::FAILURE;
     }
 
-    let config_info = match serde_json::from_str::<ConfigScraper>(
-        fs::read_to_string(config_path)
-            .expect("Unable to read file.")
-            .as_str(),
-    ) {
+    let mut config_info = match load_config(config_path) {
         Ok(config) => config,
         Err(err) => {
-            error!("Bad config file. Please fix it and then try again.\n{err}");
+            error!("{err}");
             return ExitCode::FAILURE;
         }
     };
 
     let is_verbose = config_info.verbose;
+    let shutdown_timeout = Duration::from_secs(
+        config_info
+            .shutdown_timeout_secs
+            .unwrap_or(DEFAULT_SHUTDOWN_TIMEOUT_SECS),
+    );
     info!("Loaded configuration file: {}", config_info.config_name);
+    if is_verbose {
+        // Verbose logging includes debug messages from this binary (but not its dependencies).
+        _ = log_filter_handle.modify(|filter| {
+            *filter =
+                std::mem::take(filter).with_target(env!("CARGO_CRATE_NAME"), LevelFilter::DEBUG)
+        });
+    }
+
+    let webreg_client = match config_info.webreg_client() {
+        Ok(client) => client,
+        Err(err) => {
+            error!("Unable to create the HTTP client for WebReg; is the proxy valid?\n{err}");
+            return ExitCode::FAILURE;
+        }
+    };
+
+    if !expand_current_terms(&mut config_info, &webreg_client).await {
+        error!("Unable to resolve the current term(s) from WebReg.");
+        return ExitCode::FAILURE;
+    }
+
+    if !config_info.check_request_rate() {
+        return ExitCode::FAILURE;
+    }
+
+    #[cfg(not(feature = "auth"))]
+    if config_info.rate_limit.is_some() {
+        warn!("A rate limit was configured, but the `auth` feature is not enabled.");
+    }
+
+    #[cfg(not(feature = "postgres"))]
+    if config_info.postgres_url.is_some() {
+        warn!("A PostgreSQL database was configured, but the `postgres` feature is not enabled.");
+    }
 
     // Run the tracker for each term
-    let state = Arc::new(WrapperState::new(config_info));
+    let state = Arc::new(WrapperState::new(config_info, webreg_client.clone()));
     tokio::spawn({
         let cloned_state = state.clone();
         async move {
@@ -59,6 +138,17 @@ async fn main() -> ExitCode {
         }
     });
 
+    #[cfg(unix)]
+    tokio::spawn(reload_on_sighup(
+        state.clone(),
+        config_path.to_path_buf(),
+        webreg_client,
+    ));
+
+    if let Some(policy) = state.retention.clone() {
+        tokio::spawn(run_retention(state.clone(), policy));
+    }
+
     let addr = SocketAddr::from_str(
         format!(
             "{}:{}",
@@ -76,18 +166,105 @@ async fn main() -> ExitCode {
 
     let listener = tokio::net::TcpListener::bind(&addr.unwrap()).await.unwrap();
     axum::serve(listener, create_router(state.clone()).into_make_service())
-        .with_graceful_shutdown(shutdown_signal(state))
+        .with_graceful_shutdown(shutdown_signal(state, shutdown_timeout))
         .await
         .unwrap();
     ExitCode::SUCCESS
 }
 
-/// Handles shutting down the server.
+/// Reads and parses the configuration file.
+///
+/// # Parameters
+/// - `path`: The path to the configuration file.
+///
+/// # Returns
+/// The configuration, or a message describing why it couldn't be loaded.
+fn load_config(path: &Path) -> Result<ConfigScraper, String> {
+    let contents = fs::read_to_string(path)
+        .map_err(|err| format!("Unable to read the config file.\n{err}"))?;
+    serde_json::from_str(contents.as_str())
+        .map_err(|err| format!("Bad config file. Please fix it and then try again.\n{err}"))
+}
+
+/// Reloads the terms from the configuration file. If the configuration file is invalid, the
+/// current terms are kept.
+///
+/// # Parameters
+/// - `state`: The wrapper state.
+/// - `path`: The path to the configuration file.
+/// - `webreg_client`: The client to use to make requests to WebReg, if the current term
+///   needs to be resolved.
+///
+/// # Returns
+/// `true` if the terms were reloaded, and `false` otherwise.
+async fn reload_config(
+    state: &WrapperState,
+    path: &Path,
+    webreg_client: &webweg_reqwest::Client,
+) -> bool {
+    let mut config = match load_config(path) {
+        Ok(config) => config,
+        Err(err) => {
+            warn!("Unable to reload the configuration file, so it will be ignored.\n{err}");
+            return false;
+        }
+    };
+
+    if !expand_current_terms(&mut config, webreg_client).await {
+        warn!("Unable to resolve the current term(s), so the configuration file will be ignored.");
+        return false;
+    }
+
+    if !config.check_request_rate() {
+        warn!("The reloaded configuration file will be ignored.");
+        return false;
+    }
+
+    let (added, removed) = state.reload_terms(config);
+    info!(
+        "Reloaded the configuration file (added: [{}], removed: [{}]).",
+        added.join(", "),
+        removed.join(", ")
+    );
+    true
+}
+
+/// Reloads the terms from the configuration file whenever SIGHUP is received.
+///
+/// # Parameters
+/// - `state`: The wrapper state.
+/// - `path`: The path to the configuration file.
+/// - `webreg_client`: The client to use to make requests to WebReg.
+#[cfg(unix)]
+async fn reload_on_sighup(
+    state: Arc<WrapperState>,
+    path: PathBuf,
+    webreg_client: webweg_reqwest::Client,
+) {
+    use tokio::signal::unix::{signal, SignalKind};
+
+    let mut hangup = match signal(SignalKind::hangup()) {
+        Ok(hangup) => hangup,
+        Err(err) => {
+            warn!("Unable to listen for SIGHUP, so the configuration can't be reloaded.\n{err}");
+            return;
+        }
+    };
+
+    while hangup.recv().await.is_some() {
+        info!("Received SIGHUP, reloading the configuration file.");
+        reload_config(&state, path.as_path(), &webreg_client).await;
+    }
+}
+
+/// Handles shutting down the server. If the trackers don't stop in time, the process exits
+/// without waiting for them.
 ///
 /// # Parameters
 /// - `state`: The wrapper state, which is a reference to all valid scrapers and other relevant
 ///   information.
-async fn shutdown_signal(state: Arc<WrapperState>) {
+/// - `timeout`: How long to wait for the trackers to stop.
+async fn shutdown_signal(state: Arc<WrapperState>, timeout: Duration) {
     tokio::signal::ctrl_c()
         .await
         .expect("Expected shutdown signal handler.");
@@ -95,7 +272,126 @@ async fn shutdown_signal(state: Arc<WrapperState>) {
     // Intercept ctrl_c event
     warn!("Invoked ctrl+c event, stopping the scraper and server.");
     state.set_stop_flag(true);
-    while state.is_running() {
-        tokio::time::sleep(Duration::from_secs(1)).await;
+    if !wait_for_trackers(&state, timeout).await {
+        warn!(
+            "The trackers didn't stop within {} second(s), so the process will exit anyway.",
+            timeout.as_secs()
+        );
+        std::process::exit(1);
+    }
+}
+
+/// Waits for the trackers to stop.
+///
+/// # Parameters
+/// - `state`: The wrapper state.
+/// - `timeout`: How long to wait for.
+///
+/// # Returns
+/// `true` if the trackers stopped in time, and `false` otherwise.
+async fn wait_for_trackers(state: &WrapperState, timeout: Duration) -> bool {
+    tokio::time::timeout(timeout, async {
+        while state.is_running() {
+            tokio::time::sleep(Duration::from_millis(100)).await;
+        }
+    })
+    .await
+    .is_ok()
+}
+
+#[cfg(test)]
+mod tests {
+    use serde_json::json;
+
+    use super::*;
+
+    fn make_term(term: &str, department: &str) -> serde_json::Value {
+        json!({
+            "term": term,
+            "cooldown": 3.0,
+            "searchQuery": [{ "levels": [], "departments": [department] }],
+            "saveDataToFile": false
+        })
+    }
+
+    fn write_config(path: &Path, terms: Vec<serde_json::Value>) {
+        let config = json!({
+            "configName": "test",
+            "apiBaseEndpoint": { "address": "127.0.0.1", "port": 3000 },
+            "cookieServer": { "address": "127.0.0.1", "port": 3001 },
+            "wrapperData": terms,
+            "verbose": false
+        });
+        fs::write(path, config.to_string()).unwrap();
+    }
+
+    #[tokio::test]
+    async fn test_wait_for_trackers() {
+        let state = WrapperState::builder().build();
+        assert!(wait_for_trackers(&state, Duration::from_millis(200)).await);
+
+        // A tracker that never stops shouldn't keep the process from exiting.
+        state
+            .is_running
+            .store(true, std::sync::atomic::Ordering::SeqCst);
+        let start = tokio::time::Instant::now();
+        assert!(!wait_for_trackers(&state, Duration::from_millis(200)).await);
+        assert!(start.elapsed() < Duration::from_secs(1));
+    }
+
+    #[test]
+    fn test_log_format() {
+        assert_eq!(LogFormat::Pretty, LogFormat::from_env_value(None));
+        assert_eq!(LogFormat::Pretty, LogFormat::from_env_value(Some("")));
+        assert_eq!(LogFormat::Pretty, LogFormat::from_env_value(Some("pretty")));
+        assert_eq!(LogFormat::Json, LogFormat::from_env_value(Some("json")));
+        assert_eq!(LogFormat::Json, LogFormat::from_env_value(Some(" JSON ")));
+    }
+
+    #[tokio::test]
+    async fn test_reload_config() {
+        let path = std::env::temp_dir().join(format!("webreg_reload_{}.json", std::process::id()));
+        write_config(
+            &path,
+            vec![make_term("FA24", "CSE"), make_term("WI25", "CSE")],
+        );
+        let client = webweg_reqwest::Client::new();
+        // The builder's auth database is in memory, so nothing is written to the working
+        // directory.
+        let state = WrapperState::builder().build();
+        state.reload_terms(load_config(&path).unwrap());
+        let old_fa24 = state.term("FA24").unwrap();
+        old_fa24.tracker.add_stat(100);
+
+        write_config(
+            &path,
+            vec![make_term("FA24", "MATH"), make_term("SP25", "CSE")],
+        );
+        assert!(reload_config(&state, &path, &client).await);
+        tokio::time::timeout(Duration::from_secs(1), state.reload_notify.notified())
+            .await
+            .unwrap();
+
+        let mut terms = state.terms().into_keys().collect::<Vec<_>>();
+        terms.sort();
+        assert_eq!(vec!["FA24", "SP25"], terms);
+        let new_fa24 = state.term("FA24").unwrap();
+        assert_eq!(vec!["MATH"], new_fa24.search_query[0].departments);
+        // Stats are kept for a term that is still tracked.
+        assert!(Arc::ptr_eq(&old_fa24.tracker, &new_fa24.tracker));
+        assert_eq!(
+            1,
+            new_fa24
+                .tracker
+                .num_requests
+                .load(std::sync::atomic::Ordering::SeqCst)
+        );
+
+        // An invalid configuration file leaves the terms alone.
+        fs::write(&path, "{").unwrap();
+        assert!(!reload_config(&state, &path, &client).await);
+        assert!(state.term("SP25").is_some());
+
+        fs::remove_file(path).unwrap();
     }
 }
@@ -1,2 +1,10 @@
+#[cfg(feature = "postgres")]
+pub mod postgres;
+pub mod retention;
+pub mod row;
+pub mod session;
+pub mod sqlite;
+pub mod terms;
 pub mod tracker;
-mod util;
+pub mod util;
+pub mod webhook;
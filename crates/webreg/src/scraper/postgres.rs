@@ -0,0 +1,118 @@
+use tokio_postgres::{Client, NoTls};
+use tracing::{error, info, warn};
+
+use crate::scraper::row::EnrollmentRow;
+
+/// The query used to create the table that enrollment data is written to.
+const CREATE_TABLE: &str = "CREATE TABLE IF NOT EXISTS enrollment (
+    time BIGINT NOT NULL,
+    term TEXT NOT NULL,
+    subj_course_id TEXT NOT NULL,
+    sec_code TEXT NOT NULL,
+    sec_id TEXT NOT NULL,
+    prof TEXT NOT NULL,
+    available BIGINT NOT NULL,
+    waitlist BIGINT NOT NULL,
+    total BIGINT NOT NULL,
+    enrolled_ct BIGINT NOT NULL
+)";
+
+/// The query used to insert many rows at once. Each parameter is an array holding one
+/// column, so that a whole pass can be written with a single round trip.
+const INSERT_ROWS: &str = "INSERT INTO enrollment
+    (time, term, subj_course_id, sec_code, sec_id, prof, available, waitlist, total, enrolled_ct)
+    SELECT * FROM UNNEST(
+        $1::BIGINT[], $2::TEXT[], $3::TEXT[], $4::TEXT[], $5::TEXT[],
+        $6::TEXT[], $7::BIGINT[], $8::BIGINT[], $9::BIGINT[], $10::BIGINT[]
+    )";
+
+/// A sink that writes enrollment data to a PostgreSQL database.
+pub struct PostgresSink {
+    client: Client,
+}
+
+impl PostgresSink {
+    /// Connects to the database, creating the `enrollment` table if it doesn't exist.
+    ///
+    /// # Parameters
+    /// - `url`: The connection string.
+    ///
+    /// # Returns
+    /// The sink, or `None` if the database couldn't be set up.
+    pub async fn connect(url: &str) -> Option<Self> {
+        let (client, connection) = match tokio_postgres::connect(url, NoTls).await {
+            Ok(o) => o,
+            Err(e) => {
+                error!("Unable to connect to the PostgreSQL database: '{e}'");
+                return None;
+            }
+        };
+
+        // The connection performs the actual communication with the database, so it needs
+        // to be polled on its own.
+        tokio::spawn(async move {
+            if let Err(e) = connection.await {
+                error!("The PostgreSQL connection was closed: '{e}'");
+            }
+        });
+
+        if let Err(e) = client.batch_execute(CREATE_TABLE).await {
+            error!("Unable to create the enrollment table: '{e}'");
+            return None;
+        }
+
+        info!("Connected to the PostgreSQL database.");
+        Some(Self { client })
+    }
+
+    /// Writes the given rows to the database.
+    ///
+    /// # Parameters
+    /// - `term`: The term that the rows are for.
+    /// - `rows`: The rows.
+    pub async fn insert(&self, term: &str, rows: &[EnrollmentRow]) {
+        if rows.is_empty() {
+            return;
+        }
+
+        let time = rows.iter().map(|r| r.time).collect::<Vec<_>>();
+        let term = vec![term; rows.len()];
+        let subj_course_id = rows
+            .iter()
+            .map(|r| r.subj_course_id.as_str())
+            .collect::<Vec<_>>();
+        let sec_code = rows.iter().map(|r| r.sec_code.as_str()).collect::<Vec<_>>();
+        let sec_id = rows.iter().map(|r| r.sec_id.as_str()).collect::<Vec<_>>();
+        let prof = rows.iter().map(|r| r.prof.as_str()).collect::<Vec<_>>();
+        let available = rows.iter().map(|r| r.available).collect::<Vec<_>>();
+        let waitlist = rows.iter().map(|r| r.waitlist).collect::<Vec<_>>();
+        let total = rows.iter().map(|r| r.total).collect::<Vec<_>>();
+        let enrolled_ct = rows.iter().map(|r| r.enrolled_ct).collect::<Vec<_>>();
+
+        if let Err(e) = self
+            .client
+            .execute(
+                INSERT_ROWS,
+                &[
+                    &time,
+                    &term,
+                    &subj_course_id,
+                    &sec_code,
+                    &sec_id,
+                    &prof,
+                    &available,
+                    &waitlist,
+                    &total,
+                    &enrolled_ct,
+                ],
+            )
+            .await
+        {
+            warn!(
+                "[{}] Unable to write {} row(s) to the PostgreSQL database: '{e}'",
+                term[0],
+                rows.len()
+            );
+        }
+    }
+}
@@ -0,0 +1,284 @@
+use std::fs;
+use std::path::PathBuf;
+use std::sync::Arc;
+use std::time::{Duration, SystemTime};
+
+use tracing::{info, warn};
+
+use crate::types::{ConfigRetention, WrapperState};
+
+/// The delay between each pass of the retention policy, in seconds.
+const RETENTION_CHECK_DELAY: u64 = 60 * 60;
+
+/// Runs the retention policy on a timer until the application is told to stop.
+///
+/// # Parameters
+/// - `state`: The wrapper state.
+/// - `policy`: The retention policy to enforce.
+pub async fn run_retention(state: Arc<WrapperState>, policy: ConfigRetention) {
+    while !state.should_stop() {
+        // Reading the output directories and deleting files blocks, so it shouldn't be done
+        // on the runtime's worker threads.
+        let num_removed = {
+            let state = state.clone();
+            let policy = policy.clone();
+            tokio::task::spawn_blocking(move || prune_old_outputs(&state, &policy))
+                .await
+                .unwrap_or_else(|e| {
+                    warn!("Unable to enforce the retention policy: '{e}'");
+                    0
+                })
+        };
+        if num_removed > 0 {
+            info!("Retention policy removed {num_removed} old output file(s).");
+        }
+
+        // Sleep in small increments so that we don't hold up shutting down.
+        for _ in 0..RETENTION_CHECK_DELAY {
+            if state.should_stop() {
+                break;
+            }
+
+            tokio::time::sleep(Duration::from_secs(1)).await;
+        }
+    }
+}
+
+/// Deletes any output files that violate the given retention policy. The file that a
+/// tracker is currently writing to is never deleted.
+///
+/// # Parameters
+/// - `state`: The wrapper state.
+/// - `policy`: The retention policy to enforce.
+///
+/// # Returns
+/// The number of files that were deleted.
+pub fn prune_old_outputs(state: &WrapperState, policy: &ConfigRetention) -> usize {
+    let now = SystemTime::now();
+    let mut num_removed = 0;
+    for info in state.terms().values() {
+        let Ok(dir) = fs::read_dir(&info.output_dir) else {
+            warn!(
+                "[{}] Unable to read the output directory '{}' when enforcing the retention policy.",
+                info.term,
+                info.output_dir.display()
+            );
+            continue;
+        };
+
+        let all_files = dir
+            .flatten()
+            .filter_map(|entry| {
+                let metadata = entry.metadata().ok()?;
+                let modified = metadata.modified().ok()?;
+                Some((entry.path(), modified, metadata.len()))
+            })
+            .collect::<Vec<_>>();
+
+        let current = info.current_output.lock().unwrap().clone();
+        let suffix = format!("_{}.csv", info.output_name());
+
+        // All files for this term, oldest first.
+        let mut files = all_files
+            .iter()
+            .filter(|(path, _, _)| {
+                let Some(name) = path.file_name().and_then(|name| name.to_str()) else {
+                    return false;
+                };
+
+                name.starts_with("enrollment_")
+                    && (name.ends_with(suffix.as_str())
+                        || name.ends_with(format!("{suffix}.gz").as_str()))
+                    && current.as_deref() != Some(name)
+            })
+            .collect::<Vec<_>>();
+        files.sort_by_key(|(_, modified, _)| *modified);
+
+        let mut to_remove: Vec<&PathBuf> = vec![];
+        if let Some(max_age) = policy.max_age_days {
+            let max_age = Duration::from_secs(max_age * 24 * 60 * 60);
+            files.retain(|(path, modified, _)| {
+                if now.duration_since(*modified).unwrap_or_default() > max_age {
+                    to_remove.push(path);
+                    false
+                } else {
+                    true
+                }
+            });
+        }
+
+        if let Some(max_size) = policy.max_total_size_mb {
+            let max_size = max_size * 1024 * 1024;
+            let current_size = current
+                .as_deref()
+                .and_then(|name| fs::metadata(info.output_dir.join(name)).ok())
+                .map(|metadata| metadata.len())
+                .unwrap_or(0);
+            let mut total_size = current_size + files.iter().map(|(_, _, len)| len).sum::<u64>();
+            for (path, _, len) in files {
+                if total_size <= max_size {
+                    break;
+                }
+
+                total_size -= len;
+                to_remove.push(path);
+            }
+        }
+
+        for path in to_remove {
+            match fs::remove_file(path) {
+                Ok(_) => {
+                    info!(
+                        "[{}] Removed old output file '{}'.",
+                        info.term,
+                        path.display()
+                    );
+                    num_removed += 1;
+                }
+                Err(e) => {
+                    warn!(
+                        "[{}] Unable to remove old output file '{}': '{e}'",
+                        info.term,
+                        path.display()
+                    );
+                }
+            }
+        }
+    }
+
+    num_removed
+}
+
+#[cfg(test)]
+mod tests {
+    use std::fs::File;
+    use std::io::Write;
+    use std::path::Path;
+
+    use super::*;
+    use crate::types::TermInfo;
+
+    const MB: usize = 1024 * 1024;
+
+    /// Creates an empty output directory for a test.
+    fn make_dir(name: &str) -> PathBuf {
+        let dir =
+            std::env::temp_dir().join(format!("webreg_retention_{name}_{}", std::process::id()));
+        let _ = fs::remove_dir_all(&dir);
+        fs::create_dir_all(&dir).unwrap();
+        dir
+    }
+
+    /// Writes a file of the given size that was last modified the given number of days ago.
+    fn write_file(dir: &Path, name: &str, len: usize, age_days: u64) {
+        let mut file = File::create(dir.join(name)).unwrap();
+        file.write_all(&vec![b'a'; len]).unwrap();
+        file.set_modified(SystemTime::now() - Duration::from_secs(age_days * 24 * 60 * 60))
+            .unwrap();
+    }
+
+    /// Gets the names of all files in the directory, sorted.
+    fn remaining(dir: &Path) -> Vec<String> {
+        let mut names = fs::read_dir(dir)
+            .unwrap()
+            .flatten()
+            .map(|entry| entry.file_name().into_string().unwrap())
+            .collect::<Vec<_>>();
+        names.sort();
+        names
+    }
+
+    fn make_state(dir: &Path) -> WrapperState {
+        WrapperState::builder()
+            .with_terms(vec![TermInfo {
+                output_dir: dir.to_path_buf(),
+                ..TermInfo::for_test("FA24")
+            }])
+            .build()
+    }
+
+    #[test]
+    fn test_prune_by_age() {
+        let dir = make_dir("age");
+        write_file(&dir, "enrollment_2024-01-01_FA24.csv", 10, 10);
+        write_file(&dir, "enrollment_2024-01-02_FA24.csv.gz", 10, 9);
+        write_file(&dir, "enrollment_2024-01-09_FA24.csv", 10, 1);
+        let policy = ConfigRetention {
+            max_age_days: Some(7),
+            max_total_size_mb: None,
+        };
+
+        assert_eq!(2, prune_old_outputs(&make_state(&dir), &policy));
+        assert_eq!(vec!["enrollment_2024-01-09_FA24.csv"], remaining(&dir));
+        fs::remove_dir_all(dir).unwrap();
+    }
+
+    #[test]
+    fn test_prune_by_size() {
+        let dir = make_dir("size");
+        write_file(&dir, "enrollment_2024-01-01_FA24.csv", MB, 4);
+        write_file(&dir, "enrollment_2024-01-02_FA24.csv.gz", MB, 3);
+        write_file(&dir, "enrollment_2024-01-03_FA24.csv", MB, 2);
+        write_file(&dir, "enrollment_2024-01-04_FA24.csv", MB, 1);
+        let policy = ConfigRetention {
+            max_age_days: None,
+            max_total_size_mb: Some(2),
+        };
+
+        // The oldest files are deleted until the rest fit.
+        assert_eq!(2, prune_old_outputs(&make_state(&dir), &policy));
+        assert_eq!(
+            vec![
+                "enrollment_2024-01-03_FA24.csv",
+                "enrollment_2024-01-04_FA24.csv"
+            ],
+            remaining(&dir)
+        );
+        fs::remove_dir_all(dir).unwrap();
+    }
+
+    #[test]
+    fn test_prune_keeps_current_output() {
+        let dir = make_dir("current");
+        write_file(&dir, "enrollment_2024-01-01_FA24.csv", MB, 10);
+        write_file(&dir, "enrollment_2024-01-02_FA24.csv", MB, 9);
+        write_file(&dir, "enrollment_2024-01-03_FA24.csv", MB, 8);
+        let state = make_state(&dir);
+        *state.term("FA24").unwrap().current_output.lock().unwrap() =
+            Some("enrollment_2024-01-01_FA24.csv".to_owned());
+        let policy = ConfigRetention {
+            max_age_days: Some(7),
+            max_total_size_mb: Some(1),
+        };
+
+        // Even though it's the oldest file, and too old, the file being written to is kept.
+        assert_eq!(2, prune_old_outputs(&state, &policy));
+        assert_eq!(vec!["enrollment_2024-01-01_FA24.csv"], remaining(&dir));
+        fs::remove_dir_all(dir).unwrap();
+    }
+
+    #[test]
+    fn test_prune_ignores_other_files() {
+        let dir = make_dir("other");
+        let others = [
+            "enrollment_2024-01-01_FA24.db",
+            "enrollment_2024-01-01_WI25.csv",
+            "enrollment_2024-01-01_FA24.csv.bak",
+            "notes_FA24.csv",
+        ];
+        for name in others {
+            write_file(&dir, name, MB, 30);
+        }
+        write_file(&dir, "enrollment_2024-01-01_FA24.csv", 10, 30);
+        let policy = ConfigRetention {
+            max_age_days: Some(7),
+            max_total_size_mb: Some(0),
+        };
+
+        assert_eq!(1, prune_old_outputs(&make_state(&dir), &policy));
+        let mut expected = others.to_vec();
+        expected.sort();
+        assert_eq!(expected, remaining(&dir));
+        fs::remove_dir_all(dir).unwrap();
+    }
+}
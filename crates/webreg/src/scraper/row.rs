@@ -0,0 +1,38 @@
+use webweg::types::CourseSection;
+
+/// A single row of enrollment data, matching a single line of the CSV output.
+pub struct EnrollmentRow {
+    pub time: i64,
+    pub subj_course_id: String,
+    pub sec_code: String,
+    pub sec_id: String,
+    pub prof: String,
+    pub available: i64,
+    pub waitlist: i64,
+    pub total: i64,
+    pub enrolled_ct: i64,
+}
+
+impl EnrollmentRow {
+    /// Creates a row from the given section.
+    ///
+    /// # Parameters
+    /// - `time`: The time that the data was obtained.
+    /// - `section`: The section.
+    ///
+    /// # Returns
+    /// The row.
+    pub fn new(time: i64, section: &CourseSection) -> Self {
+        Self {
+            time,
+            subj_course_id: section.subj_course_id.clone(),
+            sec_code: section.section_code.clone(),
+            sec_id: section.section_id.clone(),
+            prof: section.all_instructors.join(" & "),
+            available: section.available_seats,
+            waitlist: section.waitlist_ct,
+            total: section.total_seats,
+            enrolled_ct: section.enrolled_ct,
+        }
+    }
+}
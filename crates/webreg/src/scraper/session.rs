@@ -0,0 +1,199 @@
+use std::fmt::{Display, Formatter};
+
+use serde_json::Value;
+use webweg_reqwest::header::{COOKIE, USER_AGENT};
+
+use crate::scraper::util::{get_epoch_time, read_text_bounded};
+use crate::types::WrapperSettings;
+
+/// The endpoint that reports whether a set of session cookies are still valid.
+pub const WEBREG_PING: &str = "https://act.ucsd.edu/webreg2/svc/wradapter/secure/ping-server";
+
+/// Text that only appears on the page that WebReg redirects to when the session cookies are
+/// no longer valid (i.e., the single sign-on page).
+const LOGGED_OUT_MARKERS: [&str; 3] = ["Skip to main content", "Single Sign-On", "SAMLRequest"];
+
+/// Whether a set of session cookies can be used to make requests to WebReg.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum SessionStatus {
+    /// The cookies can be used.
+    Valid,
+    /// The cookies have expired, or were never valid; new cookies are needed.
+    Expired,
+    /// It's unclear whether the cookies can be used (e.g., because WebReg is down), for the
+    /// given reason.
+    Unknown(String),
+}
+
+impl Display for SessionStatus {
+    fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
+        match self {
+            SessionStatus::Valid => write!(f, "the session is valid"),
+            SessionStatus::Expired => write!(f, "the session has expired"),
+            SessionStatus::Unknown(reason) => {
+                write!(f, "the session is in an unknown state ({reason})")
+            }
+        }
+    }
+}
+
+/// Decides whether a set of session cookies are valid from WebReg's response to a ping.
+///
+/// # Parameters
+/// - `status`: The response's status code.
+/// - `body`: The response's body.
+///
+/// # Returns
+/// The status of the session.
+pub fn session_status(status: u16, body: &str) -> SessionStatus {
+    match status {
+        401 | 403 => return SessionStatus::Expired,
+        200 => {}
+        _ => return SessionStatus::Unknown(format!("WebReg responded with status {status}")),
+    }
+
+    if let Ok(json) = serde_json::from_str::<Value>(body) {
+        return match json["SESSION_OK"].as_bool() {
+            Some(true) => SessionStatus::Valid,
+            Some(false) => SessionStatus::Expired,
+            None => SessionStatus::Unknown(
+                "the response didn't say whether the session is OK".to_owned(),
+            ),
+        };
+    }
+
+    // If the session isn't valid, WebReg redirects to the login page.
+    if LOGGED_OUT_MARKERS
+        .iter()
+        .any(|marker| body.contains(marker))
+    {
+        return SessionStatus::Expired;
+    }
+
+    SessionStatus::Unknown("the response wasn't recognized".to_owned())
+}
+
+/// Checks whether a set of session cookies can be used to make requests to WebReg.
+///
+/// # Parameters
+/// - `settings`: The settings to make the request with.
+/// - `url`: The URL to ping; this should usually be `WEBREG_PING`.
+/// - `cookies`: The session cookies.
+/// - `max_body_size`: The maximum size of the response body, in bytes.
+///
+/// # Returns
+/// The status of the session.
+pub async fn check_session(
+    settings: &WrapperSettings,
+    url: &str,
+    cookies: &str,
+    max_body_size: usize,
+) -> SessionStatus {
+    let mut request = settings
+        .client
+        .get(url)
+        .query(&[("_", get_epoch_time())])
+        .header(COOKIE, cookies)
+        .timeout(settings.timeout);
+    if let Some(ref user_agent) = settings.user_agent {
+        request = request.header(USER_AGENT, user_agent);
+    }
+
+    let response = match request.send().await {
+        Ok(response) => response,
+        Err(e) => return SessionStatus::Unknown(format!("the request failed: {e}")),
+    };
+
+    let status = response.status().as_u16();
+    match read_text_bounded(response, max_body_size).await {
+        Ok(body) => session_status(status, body.as_str()),
+        Err(e) => SessionStatus::Unknown(format!("the response couldn't be read: {e}")),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use axum::http::StatusCode;
+    use axum::routing::get;
+    use axum::Router;
+
+    use super::*;
+
+    const LOGGED_OUT_PAGE: &str = r##"<!DOCTYPE html>
+<html lang="en">
+<head><title>UC San Diego Single Sign-On</title></head>
+<body>
+  <a href="#main-content" class="sr-only">Skip to main content</a>
+  <form method="post" action="/idp/profile/SAML2/Redirect/SSO"></form>
+</body>
+</html>"##;
+
+    #[test]
+    fn test_session_status() {
+        assert_eq!(
+            SessionStatus::Valid,
+            session_status(200, r#"{"SESSION_OK": true}"#)
+        );
+        assert_eq!(
+            SessionStatus::Expired,
+            session_status(200, r#"{"SESSION_OK": false}"#)
+        );
+        assert_eq!(SessionStatus::Expired, session_status(200, LOGGED_OUT_PAGE));
+        assert_eq!(SessionStatus::Expired, session_status(401, ""));
+        assert!(matches!(
+            session_status(503, "Service Unavailable"),
+            SessionStatus::Unknown(reason) if reason.contains("503")
+        ));
+        assert!(matches!(
+            session_status(200, "<html></html>"),
+            SessionStatus::Unknown(_)
+        ));
+    }
+
+    #[tokio::test]
+    async fn test_check_session() {
+        let router = Router::new()
+            .route(
+                "/valid",
+                get(|headers: axum::http::HeaderMap| async move {
+                    if headers.get("cookie").is_some_and(|c| c == "abc=123") {
+                        r#"{"SESSION_OK": true}"#
+                    } else {
+                        r#"{"SESSION_OK": false}"#
+                    }
+                }),
+            )
+            .route("/logged_out", get(|| async { LOGGED_OUT_PAGE }))
+            .route("/huge", get(|| async { "a".repeat(2048) }))
+            .route(
+                "/down",
+                get(|| async { (StatusCode::SERVICE_UNAVAILABLE, "Down") }),
+            );
+        let listener = tokio::net::TcpListener::bind("127.0.0.1:0").await.unwrap();
+        let base = format!("http://{}", listener.local_addr().unwrap());
+        tokio::spawn(async move { axum::serve(listener, router).await.unwrap() });
+
+        let settings = WrapperSettings::default();
+        let check = |path: &str, cookies: &'static str| {
+            let url = format!("{base}{path}");
+            let settings = settings.clone();
+            async move { check_session(&settings, url.as_str(), cookies, 1024).await }
+        };
+
+        assert_eq!(SessionStatus::Valid, check("/valid", "abc=123").await);
+        assert_eq!(SessionStatus::Expired, check("/valid", "abc=456").await);
+        assert_eq!(
+            SessionStatus::Expired,
+            check("/logged_out", "abc=123").await
+        );
+        assert!(matches!(
+            check("/down", "abc=123").await,
+            SessionStatus::Unknown(_)
+        ));
+        // The response is larger than the maximum body size, so it shouldn't be read.
+        assert!(matches!(
+            check("/huge", "abc=123").await,
+            SessionStatus::Unknown(reason) if reason.contains("maximum size")
+        ));
+    }
+}
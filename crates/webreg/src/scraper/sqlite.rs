@@ -0,0 +1,232 @@
+use std::path::Path;
+
+use rusqlite::{params, Connection, OpenFlags};
+use serde::Serialize;
+use tracing::{error, warn};
+
+use crate::scraper::row::EnrollmentRow;
+
+/// The queries used to create the table that enrollment data is written to, along with
+/// an index for looking up a course's history.
+const CREATE_TABLE: &str = "CREATE TABLE IF NOT EXISTS enrollment (
+    time INTEGER NOT NULL,
+    subj_course_id TEXT NOT NULL,
+    sec_code TEXT NOT NULL,
+    sec_id TEXT NOT NULL,
+    prof TEXT NOT NULL,
+    available INTEGER NOT NULL,
+    waitlist INTEGER NOT NULL,
+    total INTEGER NOT NULL,
+    enrolled_ct INTEGER NOT NULL
+);
+CREATE INDEX IF NOT EXISTS enrollment_course_time ON enrollment (subj_course_id, time);";
+
+/// The query used to insert a single row.
+const INSERT_ROW: &str = "INSERT INTO enrollment
+    (time, subj_course_id, sec_code, sec_id, prof, available, waitlist, total, enrolled_ct)
+    VALUES (?1, ?2, ?3, ?4, ?5, ?6, ?7, ?8, ?9)";
+
+/// The query used to get a course's history, oldest first.
+const SELECT_HISTORY: &str =
+    "SELECT time, sec_code, sec_id, available, waitlist, total, enrolled_ct
+    FROM enrollment
+    WHERE subj_course_id = ?1
+    ORDER BY time, sec_code";
+
+/// The enrollment numbers of a single section at a single point in time.
+#[derive(Serialize, Debug, PartialEq, Eq)]
+pub struct HistoryEntry {
+    pub time: i64,
+    pub sec_code: String,
+    pub sec_id: String,
+    pub available: i64,
+    pub waitlist: i64,
+    pub total: i64,
+    pub enrolled_ct: i64,
+}
+
+/// Reads the history of every section of a course from the database.
+///
+/// # Parameters
+/// - `path`: The path to the database file.
+/// - `subj_course_id`: The course (e.g., `CSE 100`).
+///
+/// # Returns
+/// The enrollment numbers of every section of the course, in time order. If the database
+/// doesn't exist yet, this will be empty.
+pub fn read_history(path: &Path, subj_course_id: &str) -> rusqlite::Result<Vec<HistoryEntry>> {
+    if !path.exists() {
+        return Ok(vec![]);
+    }
+
+    let conn = Connection::open_with_flags(path, OpenFlags::SQLITE_OPEN_READ_ONLY)?;
+    let mut stmt = conn.prepare(SELECT_HISTORY)?;
+    let entries = stmt
+        .query_map([subj_course_id], |row| {
+            Ok(HistoryEntry {
+                time: row.get(0)?,
+                sec_code: row.get(1)?,
+                sec_id: row.get(2)?,
+                available: row.get(3)?,
+                waitlist: row.get(4)?,
+                total: row.get(5)?,
+                enrolled_ct: row.get(6)?,
+            })
+        })?
+        .collect();
+    entries
+}
+
+/// A sink that writes enrollment data for a single term to a SQLite database.
+pub struct SqliteSink {
+    conn: Connection,
+}
+
+impl SqliteSink {
+    /// Opens the database, creating it and the `enrollment` table if they don't exist.
+    ///
+    /// # Parameters
+    /// - `path`: The path to the database file.
+    ///
+    /// # Returns
+    /// The sink, or `None` if the database couldn't be set up.
+    pub fn open(path: &Path) -> Option<Self> {
+        let conn = match Connection::open(path) {
+            Ok(o) => o,
+            Err(e) => {
+                error!(
+                    "Unable to open the SQLite database '{}': '{e}'",
+                    path.display()
+                );
+                return None;
+            }
+        };
+
+        if let Err(e) = conn.execute_batch(CREATE_TABLE) {
+            error!("Unable to create the enrollment table: '{e}'");
+            return None;
+        }
+
+        Some(Self { conn })
+    }
+
+    /// Writes the given rows to the database in a single transaction.
+    ///
+    /// # Parameters
+    /// - `term`: The term that the rows are for. This is only used for logging.
+    /// - `rows`: The rows.
+    pub fn insert(&mut self, term: &str, rows: &[EnrollmentRow]) {
+        if rows.is_empty() {
+            return;
+        }
+
+        if let Err(e) = self.try_insert(rows) {
+            warn!(
+                "[{term}] Unable to write {} row(s) to the SQLite database: '{e}'",
+                rows.len()
+            );
+        }
+    }
+
+    /// Writes the given rows to the database in a single transaction.
+    ///
+    /// # Parameters
+    /// - `rows`: The rows.
+    ///
+    /// # Returns
+    /// An error if any row couldn't be written, in which case no rows are written.
+    fn try_insert(&mut self, rows: &[EnrollmentRow]) -> rusqlite::Result<()> {
+        let tx = self.conn.transaction()?;
+        {
+            let mut stmt = tx.prepare_cached(INSERT_ROW)?;
+            for row in rows {
+                stmt.execute(params![
+                    row.time,
+                    row.subj_course_id,
+                    row.sec_code,
+                    row.sec_id,
+                    row.prof,
+                    row.available,
+                    row.waitlist,
+                    row.total,
+                    row.enrolled_ct,
+                ])?;
+            }
+        }
+
+        tx.commit()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn make_row(time: i64, available: i64) -> EnrollmentRow {
+        EnrollmentRow {
+            time,
+            subj_course_id: "CSE 100".to_owned(),
+            sec_code: "A01".to_owned(),
+            sec_id: "123456".to_owned(),
+            prof: "Doe, John".to_owned(),
+            available,
+            waitlist: 0,
+            total: 100,
+            enrolled_ct: 100 - available,
+        }
+    }
+
+    #[test]
+    fn test_insert() {
+        let path = std::env::temp_dir().join(format!("webreg_sqlite_{}.db", std::process::id()));
+        let mut sink = SqliteSink::open(&path).unwrap();
+        sink.insert("FA24", &[make_row(1, 10), make_row(2, 9)]);
+        sink.insert("FA24", &[]);
+        drop(sink);
+
+        // Reopening the database shouldn't lose any data.
+        let mut sink = SqliteSink::open(&path).unwrap();
+        sink.insert("FA24", &[make_row(3, 8)]);
+        let available = sink
+            .conn
+            .prepare("SELECT available FROM enrollment WHERE subj_course_id = ?1 ORDER BY time")
+            .unwrap()
+            .query_map(["CSE 100"], |row| row.get::<_, i64>(0))
+            .unwrap()
+            .collect::<rusqlite::Result<Vec<_>>>()
+            .unwrap();
+        assert_eq!(vec![10, 9, 8], available);
+
+        drop(sink);
+        std::fs::remove_file(path).unwrap();
+    }
+
+    #[test]
+    fn test_read_history() {
+        let path =
+            std::env::temp_dir().join(format!("webreg_sqlite_history_{}.db", std::process::id()));
+        assert_eq!(Ok(vec![]), read_history(&path, "CSE 100"));
+
+        let mut other = make_row(2, 50);
+        other.subj_course_id = "CSE 101".to_owned();
+        let mut sink = SqliteSink::open(&path).unwrap();
+        sink.insert(
+            "FA24",
+            &[make_row(3, 8), other, make_row(1, 10), make_row(2, 9)],
+        );
+        drop(sink);
+
+        let history = read_history(&path, "CSE 100").unwrap();
+        assert_eq!(
+            vec![(1, 10, 90), (2, 9, 91), (3, 8, 92)],
+            history
+                .iter()
+                .map(|entry| (entry.time, entry.available, entry.enrolled_ct))
+                .collect::<Vec<_>>()
+        );
+        assert_eq!("123456", history[0].sec_id);
+        assert!(read_history(&path, "MATH 20C").unwrap().is_empty());
+
+        std::fs::remove_file(path).unwrap();
+    }
+}
@@ -0,0 +1,210 @@
+use reqwest::Client;
+use tracing::{info, warn};
+use webweg::wrapper::WebRegWrapper;
+
+use crate::scraper::util::request_cookies_with_retry;
+use crate::types::{ConfigScraper, ConfigTermDatum};
+
+/// The term that, when specified in the configuration file, is resolved to all terms that
+/// are currently available on WebReg.
+pub const CURRENT_TERM: &str = "current";
+
+/// Gets all terms that are currently available on WebReg.
+///
+/// # Parameters
+/// - `wrapper`: The wrapper, which should have valid session cookies.
+///
+/// # Returns
+/// The term codes (e.g., `FA24`) of all terms available on WebReg, ordered from the
+/// oldest to the newest term. If an error occurs, this will be empty.
+pub async fn current_terms(wrapper: &WebRegWrapper) -> Vec<String> {
+    match wrapper.get_all_terms().await {
+        Ok(mut terms) => {
+            terms.sort_by_key(|term| term.seq_id);
+            terms.into_iter().map(|term| term.term_code).collect()
+        }
+        Err(e) => {
+            warn!("Unable to get all terms from WebReg: '{e}'");
+            vec![]
+        }
+    }
+}
+
+/// Expands any term in the configuration whose value is `current` into one entry for each
+/// term that is currently available on WebReg. If more than one term is available, then
+/// _all_ of them are tracked using the same settings, minus the alias (since every term
+/// needs its own output files). Terms that are already explicitly listed in the
+/// configuration are skipped.
+///
+/// Since the list of terms can only be obtained with valid session cookies, this will
+/// request session cookies from the cookie server.
+///
+/// # Parameters
+/// - `config`: The configuration to expand.
+/// - `webreg_client`: The client to use to make requests to WebReg.
+///
+/// # Returns
+/// `true` if there was nothing to expand, or if expanding was successful. `false` if the
+/// list of terms could not be obtained.
+pub async fn expand_current_terms(
+    config: &mut ConfigScraper,
+    webreg_client: &webweg_reqwest::Client,
+) -> bool {
+    if !config
+        .wrapper_data
+        .iter()
+        .any(|data| data.term.eq_ignore_ascii_case(CURRENT_TERM))
+    {
+        return true;
+    }
+
+    info!("Requesting session cookies to resolve the current term.");
+    let Some((_, cookies)) = request_cookies_with_retry(
+        &Client::new(),
+        config.cookie_server.as_slice(),
+        config.max_body_size(),
+    )
+    .await
+    else {
+        return false;
+    };
+
+    let Some(wrapper) = config
+        .wrapper_builder(webreg_client)
+        .with_cookies(cookies)
+        .try_build_wrapper()
+    else {
+        return false;
+    };
+
+    let terms = current_terms(&wrapper).await;
+    if terms.is_empty() {
+        warn!("No terms are currently available on WebReg.");
+        return false;
+    }
+
+    info!("Resolved the current term(s) to: {}", terms.join(", "));
+    config.wrapper_data = expand_terms(std::mem::take(&mut config.wrapper_data), &terms);
+    true
+}
+
+/// Expands any term whose value is `current` into one entry for each of the given terms.
+/// See [`expand_current_terms`] for more information.
+///
+/// # Parameters
+/// - `wrapper_data`: The terms from the configuration.
+/// - `terms`: The terms that are currently available on WebReg.
+///
+/// # Returns
+/// The terms that should be tracked. Terms that were explicitly listed come first.
+fn expand_terms(wrapper_data: Vec<ConfigTermDatum>, terms: &[String]) -> Vec<ConfigTermDatum> {
+    let (current, mut wrapper_data): (Vec<_>, Vec<_>) = wrapper_data
+        .into_iter()
+        .partition(|data| data.term.eq_ignore_ascii_case(CURRENT_TERM));
+
+    for datum in current {
+        for term in terms {
+            if wrapper_data.iter().any(|data| &data.term == term) {
+                continue;
+            }
+
+            wrapper_data.push(ConfigTermDatum {
+                term: term.to_owned(),
+                alias: if terms.len() == 1 {
+                    datum.alias.clone()
+                } else {
+                    None
+                },
+                ..datum.clone()
+            });
+        }
+    }
+
+    wrapper_data
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn make_datum(term: &str, alias: Option<&str>, cooldown: f64) -> ConfigTermDatum {
+        serde_json::from_value(serde_json::json!({
+            "term": term,
+            "alias": alias,
+            "cooldown": cooldown,
+            "searchQuery": [],
+            "saveDataToFile": true
+        }))
+        .unwrap()
+    }
+
+    fn summarize(data: &[ConfigTermDatum]) -> Vec<(&str, Option<&str>, f64)> {
+        data.iter()
+            .map(|d| (d.term.as_str(), d.alias.as_deref(), d.cooldown))
+            .collect()
+    }
+
+    #[test]
+    fn test_expand_single_term_keeps_alias() {
+        let data = expand_terms(
+            vec![make_datum("current", Some("now"), 2.0)],
+            &["FA24".to_owned()],
+        );
+        assert_eq!(vec![("FA24", Some("now"), 2.0)], summarize(&data));
+    }
+
+    #[test]
+    fn test_expand_multiple_terms_drops_alias() {
+        let data = expand_terms(
+            vec![make_datum("CURRENT", Some("now"), 2.0)],
+            &["FA24".to_owned(), "WI25".to_owned()],
+        );
+        assert_eq!(
+            vec![("FA24", None, 2.0), ("WI25", None, 2.0)],
+            summarize(&data)
+        );
+    }
+
+    #[test]
+    fn test_expand_keeps_explicit_terms() {
+        let explicit = vec![
+            make_datum("SP24", Some("spring"), 1.0),
+            make_datum("FA24", Some("fall"), 1.0),
+        ];
+        // Nothing to expand.
+        let data = expand_terms(explicit.clone(), &["FA24".to_owned()]);
+        assert_eq!(
+            vec![("SP24", Some("spring"), 1.0), ("FA24", Some("fall"), 1.0)],
+            summarize(&data)
+        );
+
+        // A current term that's already explicitly listed shouldn't override it.
+        let mut with_current = explicit;
+        with_current.insert(0, make_datum("current", None, 3.0));
+        let data = expand_terms(with_current, &["FA24".to_owned(), "WI25".to_owned()]);
+        assert_eq!(
+            vec![
+                ("SP24", Some("spring"), 1.0),
+                ("FA24", Some("fall"), 1.0),
+                ("WI25", None, 3.0)
+            ],
+            summarize(&data)
+        );
+    }
+
+    #[test]
+    fn test_expand_duplicate_current_terms() {
+        // Only the first `current` entry should be used for each term.
+        let data = expand_terms(
+            vec![
+                make_datum("current", None, 1.0),
+                make_datum("current", None, 2.0),
+            ],
+            &["FA24".to_owned(), "WI25".to_owned()],
+        );
+        assert_eq!(
+            vec![("FA24", None, 1.0), ("WI25", None, 1.0)],
+            summarize(&data)
+        );
+    }
+}
@@ -1,36 +1,59 @@
 use futures::stream::FuturesUnordered;
-use futures::StreamExt;
+use futures::{Stream, StreamExt};
+use std::collections::HashMap;
+use std::future::Future;
 use std::sync::atomic::{AtomicBool, Ordering};
 use std::sync::Arc;
 use std::time::Duration;
 
-use serde_json::Value;
+use chrono::{Local, NaiveDate};
+use flate2::write::GzEncoder;
+use flate2::Compression;
+use tokio::sync::RwLockWriteGuard;
 use tokio::time::Instant;
 use tracing::log::error;
-use tracing::{info, warn};
+use tracing::{debug, info, warn};
+use webweg::types::{CourseSection, SearchResultItem};
 use webweg::wrapper::input_types::{SearchRequestBuilder, SearchType};
 
-use crate::scraper::util::get_epoch_time;
-use crate::types::{TermInfo, WrapperState};
+#[cfg(feature = "postgres")]
+use crate::scraper::postgres::PostgresSink;
+use crate::scraper::row::EnrollmentRow;
+use crate::scraper::session::{check_session, SessionStatus, WEBREG_PING};
+use crate::scraper::sqlite::SqliteSink;
+use crate::scraper::util::{
+    get_course_data_url, get_epoch_time, get_search_url, request_cookies_with_retry,
+    retry_with_backoff,
+};
+use crate::scraper::webhook::{notify_seat_opened, opened_sections};
+use crate::types::{error_category, OutputSink, TermInfo, WrapperState, EMPTY_RESPONSE_CATEGORY};
 use {
-    std::fs::OpenOptions,
+    std::fs::{self, OpenOptions},
     std::io::{BufWriter, Write},
-    std::path::Path,
 };
 
-/// The number of times we should allow consecutive failure requests before attempting to get
-/// new session cookies.
-const MAX_NUM_SEARCH_REQUESTS: usize = 12;
-/// The number of times we should attempt to get new session cookies.
-const MAX_NUM_LOGIN_FAILURES: i32 = 30;
-/// The number of times we should attempt to register the session cookies.
-const MAX_NUM_REGISTER: usize = 25;
-/// The base delay when getting new session cookies. Note that, when attempting to get new
-/// session cookies, we want to use exponential backoff to ensure that if we can't get cookies
-/// the first time, we wait a bit longer before trying again.
-const BASE_DELAY_FOR_SESSION_COOKIE: f64 = 10.0;
+/// The writer for the file that the tracker writes scraped data to. If the file is
+/// compressed, dropping the writer finishes the compressed stream.
+type OutputWriter = BufWriter<Box<dyn Write + Send>>;
+
+/// The available seats, waitlist count, total seats, and enrolled count of a section.
+type SeatCounts = (i64, i64, i64, i64);
+
+/// The default number of times we should allow consecutive failure requests before attempting
+/// to get new session cookies.
+pub const MAX_NUM_SEARCH_REQUESTS: usize = 12;
+/// The default number of times we should attempt to get new session cookies.
+pub const MAX_NUM_LOGIN_FAILURES: i32 = 30;
+/// The default number of times we should attempt to register the session cookies.
+pub const MAX_NUM_REGISTER: usize = 25;
+/// The default base delay, in minutes, when getting new session cookies. Note that, when
+/// attempting to get new session cookies, we want to use exponential backoff to ensure that
+/// if we can't get cookies the first time, we wait a bit longer before trying again.
+pub const BASE_DELAY_FOR_SESSION_COOKIE: f64 = 10.0;
 /// The general delay, i.e., the delay between making requests.
 const GENERAL_DELAY: u64 = 3;
+/// How often a paused tracker checks whether it has been resumed.
+const PAUSE_POLL_INTERVAL: Duration = Duration::from_millis(500);
 
 /// Runs the WebReg tracker. This will optionally attempt to reconnect to
 /// WebReg when signed out.
@@ -40,16 +63,35 @@ const GENERAL_DELAY: u64 = 3;
 /// - `verbose`: Whether the logging should be verbose.
 pub async fn run_tracker(state: Arc<WrapperState>, verbose: bool) {
     if !try_login(&state, true).await {
-        error!("Initial login could not be completed, so the tracker will no longer run.");
-        return;
+        if state.should_stop() {
+            return;
+        }
+
+        // The cookie server might just be slow to start up, so don't give up yet.
+        warn!("Initial login could not be completed, retrying with backoff.");
+        if !try_login(&state, false).await {
+            error!("Initial login could not be completed, so the tracker will no longer run.");
+            return;
+        }
+    }
+
+    #[cfg(feature = "postgres")]
+    if let Some(ref url) = state.postgres_url {
+        match PostgresSink::connect(url).await {
+            Some(sink) => {
+                let _ = state.postgres.set(sink);
+            }
+            None => warn!("Data will not be written to the PostgreSQL database."),
+        }
     }
 
     loop {
         state.is_running.store(true, Ordering::SeqCst);
 
+        let terms = state.terms();
         let current_loop_stop_flag = Arc::new(AtomicBool::new(false));
         let mut futures = FuturesUnordered::new();
-        for term_data in state.all_terms.values() {
+        for term_data in terms.values() {
             futures.push(track_webreg_enrollment(
                 &state,
                 term_data,
@@ -59,9 +101,16 @@ pub async fn run_tracker(state: Arc<WrapperState>, verbose: bool) {
         }
 
         // Wait until ONE of the futures completed, indicating that ONE of the
-        // runners is now done.
-        futures.next().await;
-        info!("A tracker is currently done. Attempting to stop other trackers.");
+        // runners is now done, or until the configuration is reloaded.
+        let reloaded = tokio::select! {
+            _ = futures.next() => false,
+            _ = state.reload_notify.notified() => true,
+        };
+        if reloaded {
+            info!("The configuration was reloaded. Attempting to stop all trackers.");
+        } else {
+            info!("A tracker is currently done. Attempting to stop other trackers.");
+        }
         current_loop_stop_flag.store(true, Ordering::SeqCst);
         while let Some(()) = futures.next().await {
             // Do nothing.
@@ -73,6 +122,23 @@ pub async fn run_tracker(state: Arc<WrapperState>, verbose: bool) {
             break;
         }
 
+        // The session cookies are still valid after a reload, but any new terms need to
+        // be associated with them before they can be searched.
+        if reloaded {
+            for term in state.terms().keys() {
+                if terms.contains_key(term) {
+                    continue;
+                }
+
+                match state.wrapper.associate_term(term).await {
+                    Ok(_) => info!("Registered the new term '{term}'."),
+                    Err(e) => warn!("Unable to register the new term '{term}': '{e}'"),
+                }
+            }
+
+            continue;
+        }
+
         // Attempt to login again.
         if try_login(&state, false).await {
             continue;
@@ -88,6 +154,38 @@ pub async fn run_tracker(state: Arc<WrapperState>, verbose: bool) {
     info!("Quitting the tracker.");
 }
 
+/// Waits until the tracker for the given term is no longer paused.
+///
+/// # Parameters
+/// - `state`: The wrapper state.
+/// - `info`: The term information.
+/// - `current_loop_stop_flag`: Whether to stop any further requests for the current
+///   tracker.
+///
+/// # Returns
+/// `true` if the tracker should keep going, or `false` if it was stopped while waiting.
+async fn wait_while_paused(
+    state: &WrapperState,
+    info: &TermInfo,
+    current_loop_stop_flag: &AtomicBool,
+) -> bool {
+    if !info.is_paused() {
+        return true;
+    }
+
+    info!("[{}] The tracker is paused.", info.term);
+    while info.is_paused() {
+        if state.should_stop() || current_loop_stop_flag.load(Ordering::SeqCst) {
+            return false;
+        }
+
+        tokio::time::sleep(PAUSE_POLL_INTERVAL).await;
+    }
+
+    info!("[{}] The tracker was resumed.", info.term);
+    true
+}
+
 /// Tracks WebReg for enrollment information. This will continuously check specific courses for
 /// their enrollment information (number of students waitlisted/enrolled, total seats) along with
 /// basic course information and store this in a CSV file for later processing.
@@ -97,53 +195,66 @@ pub async fn run_tracker(state: Arc<WrapperState>, verbose: bool) {
 /// - `info`: The term information.
 /// - `verbose`: Whether logging should be verbose.
 /// - `current_loop_stop_flag`: Whether to stop any further requests for this function call
-///                             instance.
+///   instance.
 async fn track_webreg_enrollment(
     state: &Arc<WrapperState>,
     info: &TermInfo,
     verbose: bool,
     current_loop_stop_flag: Arc<AtomicBool>,
 ) {
-    let mut writer = {
-        let file_name = format!(
-            "enrollment_{}_{}.csv",
-            chrono::offset::Local::now().format("%FT%H_%M_%S"),
-            info.term.as_str()
-        );
-        let is_new = !Path::new(&file_name).exists();
+    let mut output_date = Local::now().date_naive();
+    let mut writer = open_output(info, output_date);
+    let mut sqlite = open_sqlite(info);
+    // Rows are written to the databases once per pass, rather than once per course.
+    let mut rows = vec![];
+    #[cfg(feature = "postgres")]
+    let collect_rows = sqlite.is_some() || state.postgres.get().is_some();
+    #[cfg(not(feature = "postgres"))]
+    let collect_rows = sqlite.is_some();
+    // The seat counts of every section as of when it was last saved, if only changes are
+    // saved.
+    let mut last_seen = HashMap::new();
+    // The available seats of every watched section as of when it was last seen.
+    let mut last_available = HashMap::new();
 
-        let f = OpenOptions::new()
-            .append(true)
-            .create(true)
-            .open(&file_name)
-            .unwrap_or_else(|_| panic!("could not open or create '{file_name}'"));
-
-        let mut w = BufWriter::new(f);
-        if is_new {
-            writeln!(
-                w,
-                "time,subj_course_id,sec_code,sec_id,prof,available,waitlist,total,enrolled_ct"
-            )
-            .unwrap();
+    let mut fail_count = 0;
+    'main: loop {
+        if let Some(ref mut w) = writer {
+            w.flush().unwrap();
         }
 
-        w
-    };
+        if !wait_while_paused(state, info, &current_loop_stop_flag).await {
+            break;
+        }
 
-    let mut fail_count = 0;
-    'main: loop {
-        writer.flush().unwrap();
         let results = {
             let mut r = vec![];
-            for search_query in &info.search_query {
-                let mut temp = state
-                    .wrapper
-                    .req(info.term.as_str())
-                    .parsed()
-                    // TODO: Remove .clone usage here.
-                    .search_courses(SearchType::Advanced(search_query.clone()))
-                    .await
-                    .unwrap_or_default();
+            for search_type in search_types(info) {
+                if verbose {
+                    match search_type {
+                        SearchType::Advanced(ref search_query) => debug!(
+                            "[{}] Searching for courses: {}",
+                            info.term,
+                            get_search_url(search_query, info.term.as_str())
+                        ),
+                        _ => debug!(
+                            "[{}] Searching for sections: {}",
+                            info.term,
+                            info.watched_sections.join(", ")
+                        ),
+                    }
+                }
+
+                let mut temp = {
+                    let _cookies = state.cookie_generation.read().await;
+                    state
+                        .wrapper
+                        .req(info.term.as_str())
+                        .parsed()
+                        .search_courses(search_type)
+                        .await
+                        .unwrap_or_default()
+                };
 
                 r.append(&mut temp);
                 tokio::time::sleep(Duration::from_secs(1)).await;
@@ -163,14 +274,24 @@ async fn track_webreg_enrollment(
             results.len()
         );
 
-        for r in results {
+        // Up to `concurrency` requests are in flight at any time, and each request is started
+        // `cooldown` seconds after the previous one, so the cooldown acts as the minimum
+        // spacing between requests regardless of the concurrency.
+        let spacing = || Duration::from_secs_f64(info.cooldown());
+        // The courses are referred to by index, since the tracker's future can't be proven
+        // to be `Send` if the stream's items are references.
+        let mut requests = spaced_requests(0..results.len(), info.concurrency, &spacing, |idx| {
+            get_enrollment_count(state, info, verbose, &results[idx])
+        });
+
+        loop {
             // If the stop flag is set so that the scraper itself should STOP, or we just need
             // to stop for this iteration, then break out
             if state.should_stop() || current_loop_stop_flag.load(Ordering::SeqCst) {
                 break 'main;
             }
 
-            if fail_count != 0 && fail_count > MAX_NUM_SEARCH_REQUESTS {
+            if fail_count != 0 && fail_count > state.thresholds.max_search_failures {
                 warn!(
                     "[{}] Too many failures when trying to request data from WebReg.",
                     info.term
@@ -178,19 +299,23 @@ async fn track_webreg_enrollment(
                 break 'main;
             }
 
-            // Start timing.
-            let start_time = Instant::now();
+            rotate_output(
+                info,
+                &mut writer,
+                &mut output_date,
+                Local::now().date_naive(),
+            );
 
-            let res = state
-                .wrapper
-                .req(info.term.as_str())
-                .parsed()
-                .get_enrollment_count(r.subj_code.trim(), r.course_code.trim())
-                .await;
+            let Some((r, res, end_time)) = requests.next().await else {
+                break;
+            };
 
+            let mut had_failure = false;
             match res {
                 Err(e) => {
+                    had_failure = true;
                     fail_count += 1;
+                    info.tracker.add_error(error_category(&e));
                     warn!(
                         "[{}] An error occurred ({}). Skipping. (FAIL_COUNT: {})",
                         info.term, e, fail_count
@@ -207,28 +332,35 @@ async fn track_webreg_enrollment(
                         );
                     }
 
-                    let time = get_epoch_time();
-                    // Write to raw CSV dataset
-                    r.iter().for_each(|c| {
-                        writeln!(
-                            writer,
-                            "{},{},{},{},{},{},{},{},{}",
-                            time,
-                            c.subj_course_id,
-                            c.section_code,
-                            c.section_id,
-                            // Every instructor name (except staff) has a comma
-                            c.all_instructors.join(" & ").replace(',', ";"),
-                            c.available_seats,
-                            c.waitlist_ct,
-                            c.total_seats,
-                            c.enrolled_ct,
-                        )
-                        .unwrap()
-                    });
+                    let r = tracked_sections(&info.watched_sections, r);
+
+                    if let Some(ref url) = info.notify_webhook {
+                        for section in
+                            opened_sections(&mut last_available, &info.notify_sections, &r)
+                        {
+                            // Notifying the webhook shouldn't hold up the scraper.
+                            tokio::spawn(notify_seat_opened(
+                                state.client.clone(),
+                                url.clone(),
+                                info.term.clone(),
+                                section.clone(),
+                            ));
+                        }
+                    }
+
+                    record_sections(
+                        info,
+                        &mut last_seen,
+                        &r,
+                        get_epoch_time(),
+                        writer.as_mut(),
+                        collect_rows.then_some(&mut rows),
+                    );
                 }
                 _ => {
+                    had_failure = true;
                     fail_count += 1;
+                    info.tracker.add_error(EMPTY_RESPONSE_CATEGORY);
                     warn!(
                         "[{}] Course {} {} not found. Were you logged out? (FAIL_COUNT: {}).",
                         info.term,
@@ -240,15 +372,25 @@ async fn track_webreg_enrollment(
             }
 
             // Record time spent on request.
-            let end_time = start_time.elapsed();
             info.tracker.add_stat(end_time.as_millis() as usize);
 
-            // Sleep between requests so we don't get ourselves banned by webreg
-            tokio::time::sleep(Duration::from_secs_f64(info.cooldown)).await;
+            // The next requests are spaced out by the adapted cooldown, so we don't get
+            // ourselves banned by webreg
+            info.adapt_cooldown(had_failure);
         }
+
+        write_rows(state, info, sqlite.as_mut(), &mut rows).await;
+        // The next pass starts with a search, which should also be spaced out.
+        tokio::time::sleep(spacing()).await;
     }
 
     // Out of loop, this should run only if we need to exit the scraper (e.g., need to log back in)
+    write_rows(state, info, sqlite.as_mut(), &mut rows).await;
+
+    let Some(mut writer) = writer else {
+        return;
+    };
+
     if !writer.buffer().is_empty() {
         info!(
             "[{}] Buffer not empty! Buffer has length {}.",
@@ -258,6 +400,7 @@ async fn track_webreg_enrollment(
     }
 
     writer.flush().unwrap();
+    *info.current_output.lock().unwrap() = None;
     // Debugging possible issues with the buffer
     info!(
         "[{}] Buffer flushed. Final buffer length: {}.",
@@ -266,6 +409,380 @@ async fn track_webreg_enrollment(
     );
 }
 
+/// Gets the searches that should be made to find the courses to track for the given term.
+///
+/// # Parameters
+/// - `info`: The term information.
+///
+/// # Returns
+/// A single search for the tracked sections, if only specific sections are tracked, or one
+/// search for each search query otherwise.
+fn search_types(info: &TermInfo) -> Vec<SearchType> {
+    if !info.watched_sections.is_empty() {
+        return vec![SearchType::ByMultipleSections(
+            info.watched_sections.clone(),
+        )];
+    }
+
+    info.search_query
+        .iter()
+        // TODO: Remove .clone usage here.
+        .map(|query| SearchType::Advanced(query.clone()))
+        .collect()
+}
+
+/// Gets the sections of a course that should be tracked. Getting a course's enrollment
+/// count returns all of its sections, so if only specific sections are tracked, the rest
+/// need to be left out.
+///
+/// # Parameters
+/// - `watched_sections`: The IDs of the only sections to track, or nothing if all sections
+///   are tracked.
+/// - `sections`: The course's sections.
+///
+/// # Returns
+/// The sections that should be tracked.
+fn tracked_sections(
+    watched_sections: &[String],
+    sections: Vec<CourseSection>,
+) -> Vec<CourseSection> {
+    if watched_sections.is_empty() {
+        return sections;
+    }
+
+    sections
+        .into_iter()
+        .filter(|section| watched_sections.contains(&section.section_id))
+        .collect()
+}
+
+/// Opens the file that the tracker should write the scraped data for the given term and
+/// day to, writing the CSV header if the file is new. If the file already exists (e.g., the
+/// scraper was restarted), data is appended to it; for compressed files, this appends a new
+/// gzip member, which gzip tools read as part of the same file.
+///
+/// # Parameters
+/// - `info`: The term information.
+/// - `date`: The day that the data is for.
+///
+/// # Returns
+/// The writer for the file, or `None` if data for this term shouldn't be saved to a CSV
+/// file or the file couldn't be opened.
+fn open_output(info: &TermInfo, date: NaiveDate) -> Option<OutputWriter> {
+    if !info.should_save || info.sink != OutputSink::Csv {
+        return None;
+    }
+
+    if !create_output_dir(info) {
+        return None;
+    }
+
+    let file_name = format!(
+        "enrollment_{}_{}.csv{}",
+        date.format("%F"),
+        info.output_name(),
+        if info.compress { ".gz" } else { "" }
+    );
+    let path = info.output_dir.join(&file_name);
+    let is_new = !path.exists();
+
+    let f = match OpenOptions::new().append(true).create(true).open(&path) {
+        Ok(f) => f,
+        Err(e) => {
+            error!(
+                "[{}] Unable to open or create '{}', so data won't be saved: '{e}'",
+                info.term,
+                path.display()
+            );
+            return None;
+        }
+    };
+
+    let mut w: OutputWriter = BufWriter::new(if info.compress {
+        Box::new(GzEncoder::new(f, Compression::default()))
+    } else {
+        Box::new(f)
+    });
+    if is_new {
+        writeln!(
+            w,
+            "time,subj_course_id,sec_code,sec_id,prof,available,waitlist,total,enrolled_ct"
+        )
+        .unwrap();
+    }
+
+    *info.current_output.lock().unwrap() = Some(file_name);
+    Some(w)
+}
+
+/// Makes a request for each of the given items, with at most `concurrency` requests in
+/// flight at any time. Each request is started at least `spacing` after the previous one
+/// was started, no matter how many requests are in flight.
+///
+/// # Parameters
+/// - `items`: The items to make requests for.
+/// - `concurrency`: The maximum number of requests that can be in flight.
+/// - `spacing`: Gets the minimum time between the starts of two requests. This is called
+///   each time a request is started, so the spacing can change between requests.
+/// - `request`: Makes the request for an item.
+///
+/// # Returns
+/// The results of the requests, in the order that the requests finish.
+fn spaced_requests<'a, T, Fut>(
+    items: impl IntoIterator<Item = T> + 'a,
+    concurrency: usize,
+    spacing: &'a (impl Fn() -> Duration + Sync),
+    mut request: impl FnMut(T) -> Fut + 'a,
+) -> impl Stream<Item = Fut::Output> + 'a
+where
+    T: 'a,
+    Fut: Future + 'a,
+{
+    let next_start = Arc::new(tokio::sync::Mutex::new(Instant::now()));
+    futures::stream::iter(items)
+        .map(move |item| {
+            let next_start = next_start.clone();
+            // The request isn't started until it's polled.
+            let request = request(item);
+            async move {
+                // Holding the lock while waiting makes the requests start in order.
+                {
+                    let mut next_start = next_start.lock().await;
+                    tokio::time::sleep_until(*next_start).await;
+                    *next_start = Instant::now() + spacing();
+                }
+
+                request.await
+            }
+        })
+        .buffer_unordered(concurrency.max(1))
+}
+
+/// Gets the enrollment counts of the sections of a course that was found by a search.
+///
+/// # Parameters
+/// - `state`: The wrapper state.
+/// - `info`: The term information.
+/// - `verbose`: Whether logging should be verbose.
+/// - `course`: The course.
+///
+/// # Returns
+/// The course, the result of the request, and how long the request took.
+async fn get_enrollment_count<'a>(
+    state: &WrapperState,
+    info: &TermInfo,
+    verbose: bool,
+    course: &'a SearchResultItem,
+) -> (
+    &'a SearchResultItem,
+    webweg::types::Result<Vec<CourseSection>>,
+    Duration,
+) {
+    if verbose {
+        debug!(
+            "[{}] Getting enrollment count: {}",
+            info.term,
+            get_course_data_url(
+                course.subj_code.trim(),
+                course.course_code.trim(),
+                info.term.as_str()
+            )
+        );
+    }
+
+    // Waiting for the cookies to be replaced shouldn't count towards the time spent on the
+    // request.
+    let _cookies = state.cookie_generation.read().await;
+    // Start timing.
+    let start_time = Instant::now();
+    let res = state
+        .wrapper
+        .req(info.term.as_str())
+        .parsed()
+        .get_enrollment_count(course.subj_code.trim(), course.course_code.trim())
+        .await;
+
+    (course, res, start_time.elapsed())
+}
+
+/// Records the scraped sections of a term, writing them to the CSV output and collecting
+/// them as rows for the databases.
+///
+/// # Parameters
+/// - `info`: The term information.
+/// - `last_seen`: The seat counts of every section as of when it was last seen.
+/// - `sections`: The scraped sections.
+/// - `time`: The time that the sections were scraped.
+/// - `writer`: The CSV output, if the term is saved to one.
+/// - `rows`: The rows to write to the databases, if the term is saved to any.
+fn record_sections(
+    info: &TermInfo,
+    last_seen: &mut HashMap<String, SeatCounts>,
+    sections: &[CourseSection],
+    time: i64,
+    writer: Option<&mut OutputWriter>,
+    rows: Option<&mut Vec<EnrollmentRow>>,
+) {
+    let sections = if info.only_on_change {
+        changed_sections(last_seen, sections)
+    } else {
+        sections.iter().collect()
+    };
+
+    if let Some(rows) = rows {
+        rows.extend(sections.iter().map(|c| EnrollmentRow::new(time, c)));
+    }
+
+    if let Some(writer) = writer {
+        // Write to raw CSV dataset
+        sections.iter().for_each(|c| {
+            writeln!(
+                writer,
+                "{},{},{},{},{},{},{},{},{}",
+                time,
+                c.subj_course_id,
+                c.section_code,
+                c.section_id,
+                // Every instructor name (except staff) has a comma
+                c.all_instructors.join(" & ").replace(',', ";"),
+                c.available_seats,
+                c.waitlist_ct,
+                c.total_seats,
+                c.enrolled_ct,
+            )
+            .unwrap()
+        });
+    }
+}
+
+/// Writes the collected rows of a term to the databases, and then clears them.
+///
+/// # Parameters
+/// - `state`: The wrapper state.
+/// - `info`: The term information.
+/// - `sqlite`: The term's SQLite database, if any.
+/// - `rows`: The collected rows.
+async fn write_rows(
+    #[cfg_attr(not(feature = "postgres"), allow(unused_variables))] state: &WrapperState,
+    info: &TermInfo,
+    sqlite: Option<&mut SqliteSink>,
+    rows: &mut Vec<EnrollmentRow>,
+) {
+    if let Some(sink) = sqlite {
+        sink.insert(info.term.as_str(), rows);
+    }
+    #[cfg(feature = "postgres")]
+    if let Some(sink) = state.postgres.get() {
+        sink.insert(info.term.as_str(), rows).await;
+    }
+    rows.clear();
+}
+
+/// Creates the directory that the tracker's output files for the given term are written to,
+/// if it doesn't exist.
+///
+/// # Parameters
+/// - `info`: The term information.
+///
+/// # Returns
+/// `true` if the directory exists, and `false` if it couldn't be created.
+fn create_output_dir(info: &TermInfo) -> bool {
+    match fs::create_dir_all(&info.output_dir) {
+        Ok(()) => true,
+        Err(e) => {
+            error!(
+                "[{}] Unable to create the output directory '{}', so data won't be saved: '{e}'",
+                info.term,
+                info.output_dir.display()
+            );
+            false
+        }
+    }
+}
+
+/// Opens the SQLite database that the tracker should write the scraped data for the given
+/// term to. Each term has its own database.
+///
+/// # Parameters
+/// - `info`: The term information.
+///
+/// # Returns
+/// The database, or `None` if data for this term shouldn't be saved to a SQLite database
+/// or the database couldn't be opened.
+fn open_sqlite(info: &TermInfo) -> Option<SqliteSink> {
+    if !info.should_save || info.sink != OutputSink::Sqlite {
+        return None;
+    }
+
+    if !create_output_dir(info) {
+        return None;
+    }
+
+    SqliteSink::open(&info.sqlite_path())
+}
+
+/// Finds the sections whose seat counts have changed since they were last seen.
+///
+/// # Parameters
+/// - `last_seen`: The seat counts of every section as of when it was last seen. This is
+///   updated with the given sections.
+/// - `sections`: The sections.
+///
+/// # Returns
+/// The sections whose seat counts have changed, including sections that haven't been seen
+/// before.
+fn changed_sections<'a>(
+    last_seen: &mut HashMap<String, SeatCounts>,
+    sections: &'a [CourseSection],
+) -> Vec<&'a CourseSection> {
+    sections
+        .iter()
+        .filter(|section| {
+            let counts = (
+                section.available_seats,
+                section.waitlist_ct,
+                section.total_seats,
+                section.enrolled_ct,
+            );
+            last_seen.insert(section.section_id.clone(), counts) != Some(counts)
+        })
+        .collect()
+}
+
+/// Rolls the writer over to a new file if the day has changed since the current file was
+/// opened.
+///
+/// # Parameters
+/// - `info`: The term information.
+/// - `writer`: The writer for the current file, if any.
+/// - `output_date`: The day that the current file is for.
+/// - `today`: The current day.
+///
+/// # Returns
+/// Whether the writer was rolled over.
+fn rotate_output(
+    info: &TermInfo,
+    writer: &mut Option<OutputWriter>,
+    output_date: &mut NaiveDate,
+    today: NaiveDate,
+) -> bool {
+    if *output_date == today {
+        return false;
+    }
+
+    if let Some(ref mut w) = writer {
+        w.flush().unwrap();
+    }
+
+    info!(
+        "[{}] Rolling over to the output file for {today}.",
+        info.term
+    );
+    *writer = open_output(info, today);
+    *output_date = today;
+    true
+}
+
 /// Attempts to run the login script to get new session cookies, and then ensures that the
 /// cookies themselves are valid.
 ///
@@ -278,28 +795,30 @@ async fn track_webreg_enrollment(
 /// make requests again. `false` otherwise.
 async fn try_login(state: &Arc<WrapperState>, is_init: bool) -> bool {
     info!("Attempting to get new WebReg session cookies.");
-    let address = format!(
-        "{}:{}",
-        state.cookie_server.address, state.cookie_server.port
-    );
+
+    let max_failures = if is_init {
+        state.initial_login_attempts
+    } else {
+        state.thresholds.max_login_failures
+    };
 
     let mut num_failures = 0;
-    while num_failures <= MAX_NUM_LOGIN_FAILURES {
+    while num_failures <= max_failures {
         if is_init {
-            // If this is the initial login, then we can just wait (3 * number of failure) minutes
+            // If this is the initial login, then we can just wait 3 minutes between attempts.
             if num_failures != 0 {
                 info!(
-                    "Waiting 3 minutes before making request for new cookies ({num_failures}/{MAX_NUM_LOGIN_FAILURES})."
+                    "Waiting 3 minutes before making request for new cookies ({num_failures}/{max_failures})."
                 );
                 tokio::time::sleep(Duration::from_secs(3 * 60)).await;
             }
         } else {
-            // "Exponential" backoff formula of f(x) = 1.1^x * 10 minutes
+            // "Exponential" backoff formula of f(x) = 1.1^x * (base delay) minutes
             // delay_time represents the time we should wait before making another request in
             // *minutes*, not *seconds*.
-            let delay_time = 1.1_f64.powi(num_failures) * BASE_DELAY_FOR_SESSION_COOKIE;
+            let delay_time = 1.1_f64.powi(num_failures) * state.thresholds.login_base_delay_mins;
             info!(
-                "Waiting {delay_time} minutes before making request for new cookies ({num_failures}/{MAX_NUM_LOGIN_FAILURES})."
+                "Waiting {delay_time} minutes before making request for new cookies ({num_failures}/{max_failures})."
             );
             tokio::time::sleep(Duration::from_secs_f64(delay_time * 60.0)).await;
         }
@@ -309,35 +828,15 @@ async fn try_login(state: &Arc<WrapperState>, is_init: bool) -> bool {
             break;
         }
 
-        info!("Making a request to the cookie server (http://{address}/cookie) to get session cookies.");
-        let data = match state
-            .client
-            .get(format!("http://{address}/cookie"))
-            .send()
-            .await
-        {
-            Ok(o) => o,
-            Err(e) => {
-                warn!("Failed to connect to the cookie server; reason: '{e}'");
-                num_failures += 1;
-                continue;
-            }
-        };
-
-        let Ok(text) = data.text().await else {
-            warn!("An unknown error occurred when making a request to the cookie server.");
+        let Some((idx, cookies)) =
+            request_cookies_with_retry(&state.client, &state.cookie_servers, state.max_body_size)
+                .await
+        else {
+            warn!("None of the cookie servers provided session cookies.");
             num_failures += 1;
             continue;
         };
-
-        let json: Value = serde_json::from_str(text.as_str()).unwrap_or_default();
-        info!("Received response from cookie server: '{json}'");
-        if !json["cookie"].is_string() {
-            warn!("The 'cookie' key from the response is not valid.");
-            continue;
-        }
-
-        let cookies = json["cookie"].as_str().unwrap().to_string();
+        state.current_cookie_server.store(idx, Ordering::SeqCst);
 
         // Update the cookies for the general wrapper, but also authenticate the cookies.
         // Remember, we're sharing the same cookies.
@@ -346,7 +845,7 @@ async fn try_login(state: &Arc<WrapperState>, is_init: bool) -> bool {
             return true;
         }
 
-        warn!("An unknown error occurred when trying to authenticate the cookies.");
+        warn!("The cookies could not be authenticated for all terms specified.");
         num_failures += 1;
     }
 
@@ -365,75 +864,516 @@ async fn try_login(state: &Arc<WrapperState>, is_init: bool) -> bool {
 /// `true` if the login process is successful, indicating that the wrapper is ready to
 /// make requests again. `false` otherwise.
 #[inline]
-async fn login_with_cookies(state: &Arc<WrapperState>, cookies: &str) -> bool {
-    state.wrapper.set_cookies(cookies);
+pub async fn login_with_cookies(state: &Arc<WrapperState>, cookies: &str) -> bool {
+    // No new requests are made by the tracker until the new cookies have been registered.
+    let _generation = replace_cookies(state, cookies).await;
+    let max_register = state.thresholds.max_register_attempts;
 
-    let mut num_tries = 0;
-    while num_tries <= MAX_NUM_REGISTER {
+    // There's no point in trying to register cookies that WebReg has already rejected.
+    match check_session(
+        &state.wrapper_settings,
+        WEBREG_PING,
+        cookies,
+        state.max_body_size,
+    )
+    .await
+    {
+        SessionStatus::Valid => info!("The session cookies are valid."),
+        SessionStatus::Expired => {
+            warn!("The session cookies can't be used, since the session has expired.");
+            return false;
+        }
+        status @ SessionStatus::Unknown(_) => {
+            warn!("Unable to check the session cookies, since {status}. Trying to use them anyway.")
+        }
+    }
+
+    // WebReg sometimes can't register the cookies for a moment, so try again with backoff.
+    let registered = retry_with_backoff(
+        || register_and_check_terms(state),
+        max_register,
+        Duration::from_secs(GENERAL_DELAY),
+    )
+    .await;
+
+    if let Err(e) = registered {
+        let status = check_session(
+            &state.wrapper_settings,
+            WEBREG_PING,
+            cookies,
+            state.max_body_size,
+        )
+        .await;
+        warn!("Unable to register the session cookies ({e}); {status}.");
+        return false;
+    }
+
+    true
+}
+
+/// Registers all terms for the wrapper's session cookies, and then checks that requests
+/// can be made for every term that is being tracked.
+///
+/// # Parameters
+/// - `state`: The wrapper state.
+///
+/// # Returns
+/// `Ok(())` if courses could be found for every term, or the reason why they couldn't be.
+async fn register_and_check_terms(state: &WrapperState) -> Result<(), String> {
+    info!("Attempting to register all terms for the given session cookies.");
+    state
+        .wrapper
+        .register_all_terms()
+        .await
+        .map_err(|e| format!("unable to register all terms: '{e}'"))?;
+
+    info!("All terms for the cookies were registered. Now, checking that requests can be made.");
+    // To ensure that login was successful, try to get all courses and ensure those courses
+    // are not empty for all terms.
+    for term in state.terms().keys() {
+        // Wait a few seconds before looping.
         tokio::time::sleep(Duration::from_secs(GENERAL_DELAY)).await;
+        // Try to associate this term in particular, it's possible that this term might not
+        // be on the list of all terms because it is hidden.
+        state
+            .wrapper
+            .associate_term(term)
+            .await
+            .map_err(|e| format!("unable to register term '{term}': '{e}'"))?;
 
-        info!("Attempting to register all terms for the given session cookies.");
-        if let Err(e) = state.wrapper.register_all_terms().await {
-            num_tries += 1;
-            warn!(
-                "An error occurred when trying to register all terms ({num_tries}/{MAX_NUM_REGISTER}): '{e}'"
-            );
-            continue;
+        // Wait a few seconds before making another request.
+        tokio::time::sleep(Duration::from_secs(GENERAL_DELAY)).await;
+        let all_courses = state
+            .wrapper
+            .req(term)
+            .parsed()
+            .search_courses(SearchType::Advanced(SearchRequestBuilder::new()))
+            .await
+            .map_err(|e| format!("unable to fetch courses for term '{term}': '{e}'"))?;
+        info!("Found {} courses for the term '{term}'.", all_courses.len());
+
+        if all_courses.is_empty() {
+            return Err(format!("no courses were found for the term '{term}'"));
+        }
+    }
+
+    Ok(())
+}
+
+/// Replaces the wrapper's session cookies once every request that the tracker is making
+/// with the old cookies has finished.
+///
+/// # Parameters
+/// - `state`: The wrapper state.
+/// - `cookies`: The new session cookies.
+///
+/// # Returns
+/// A guard over the new cookie generation. The tracker won't make any requests until this
+/// is dropped, so it should be held until the new cookies are ready to be used.
+async fn replace_cookies<'a>(state: &'a WrapperState, cookies: &str) -> RwLockWriteGuard<'a, u64> {
+    let mut generation = state.cookie_generation.write().await;
+    state.wrapper.set_cookies(cookies);
+    *generation += 1;
+    info!("Replaced the session cookies (generation {}).", *generation);
+    generation
+}
+
+#[cfg(test)]
+mod tests {
+    use std::path::PathBuf;
+    use std::sync::atomic::AtomicUsize;
+
+    use super::*;
+    use crate::scraper::sqlite::read_history;
+
+    fn make_info(alias: &str, should_save: bool, output_dir: PathBuf) -> TermInfo {
+        make_info_with_compression(alias, should_save, false, output_dir)
+    }
+
+    fn make_info_with_compression(
+        alias: &str,
+        should_save: bool,
+        compress: bool,
+        output_dir: PathBuf,
+    ) -> TermInfo {
+        TermInfo {
+            alias: Some(alias.to_owned()),
+            should_save,
+            compress,
+            sink: OutputSink::Csv,
+            output_dir,
+            ..TermInfo::for_test("TEST")
+        }
+    }
+
+    #[test]
+    fn test_open_output_disabled() {
+        let info = make_info("test_open_output_disabled", false, PathBuf::from("."));
+
+        assert!(open_output(&info, Local::now().date_naive()).is_none());
+        assert!(info.current_output.lock().unwrap().is_none());
+        let created = std::fs::read_dir(".").unwrap().flatten().any(|entry| {
+            entry
+                .file_name()
+                .to_string_lossy()
+                .ends_with("_test_open_output_disabled.csv")
+        });
+        assert!(!created);
+    }
+
+    #[test]
+    fn test_open_output_dir() {
+        let output_dir = std::env::temp_dir().join(format!("webreg_output_{}", std::process::id()));
+        let info = make_info("test_open_output_dir", true, output_dir.join("nested"));
+
+        drop(open_output(&info, Local::now().date_naive()).unwrap());
+        let file_name = info.current_output.lock().unwrap().clone().unwrap();
+        let contents = fs::read_to_string(output_dir.join("nested").join(file_name)).unwrap();
+        assert!(contents.starts_with("time,subj_course_id"));
+
+        fs::remove_dir_all(output_dir).unwrap();
+    }
+
+    #[test]
+    fn test_rotate_output() {
+        let output_dir = std::env::temp_dir().join(format!("webreg_rotate_{}", std::process::id()));
+        let info = make_info("test_rotate_output", true, output_dir.clone());
+        let day1 = NaiveDate::from_ymd_opt(2024, 10, 1).unwrap();
+        let day2 = NaiveDate::from_ymd_opt(2024, 10, 2).unwrap();
+
+        let mut output_date = day1;
+        let mut writer = open_output(&info, output_date);
+        assert!(!rotate_output(&info, &mut writer, &mut output_date, day1));
+        assert!(rotate_output(&info, &mut writer, &mut output_date, day2));
+        assert_eq!(day2, output_date);
+        assert_eq!(
+            Some("enrollment_2024-10-02_test_rotate_output.csv"),
+            info.current_output.lock().unwrap().as_deref()
+        );
+        drop(writer);
+
+        assert!(output_dir
+            .join("enrollment_2024-10-01_test_rotate_output.csv")
+            .exists());
+        assert!(output_dir
+            .join("enrollment_2024-10-02_test_rotate_output.csv")
+            .exists());
+
+        // Reopening an existing day's file shouldn't write the header again.
+        drop(open_output(&info, day1));
+        let contents =
+            fs::read_to_string(output_dir.join("enrollment_2024-10-01_test_rotate_output.csv"))
+                .unwrap();
+        assert_eq!(1, contents.lines().count());
+
+        fs::remove_dir_all(output_dir).unwrap();
+    }
+
+    #[test]
+    fn test_open_output_bad_dir() {
+        // The output directory can't be created, since a file is in the way.
+        let file = std::env::temp_dir().join(format!("webreg_bad_dir_{}", std::process::id()));
+        fs::write(&file, "").unwrap();
+        let output_dir = file.join("output");
+
+        let info = make_info("test_bad_dir", true, output_dir.clone());
+        let date = NaiveDate::from_ymd_opt(2024, 10, 1).unwrap();
+        assert!(open_output(&info, date).is_none());
+        assert!(info.current_output.lock().unwrap().is_none());
+
+        let info = TermInfo {
+            should_save: true,
+            sink: OutputSink::Sqlite,
+            output_dir,
+            ..TermInfo::for_test("TEST")
         };
+        assert!(open_sqlite(&info).is_none());
 
-        info!(
-            "All terms for the cookies were registered. Now, checking that requests can be made."
+        fs::remove_file(file).unwrap();
+    }
+
+    #[test]
+    fn test_open_output_compressed() {
+        use std::io::Read;
+
+        let output_dir =
+            std::env::temp_dir().join(format!("webreg_compress_{}", std::process::id()));
+        let info = make_info_with_compression("test_compressed", true, true, output_dir.clone());
+        let date = NaiveDate::from_ymd_opt(2024, 10, 1).unwrap();
+
+        // Write to the file twice, as if the scraper was restarted in between.
+        for row in [
+            "1,CSE 100,A01,1,Prof,0,0,100,100",
+            "2,CSE 100,A01,1,Prof,1,0,100,99",
+        ] {
+            let mut writer = open_output(&info, date).unwrap();
+            writeln!(writer, "{row}").unwrap();
+            writer.flush().unwrap();
+        }
+
+        let path = output_dir.join("enrollment_2024-10-01_test_compressed.csv.gz");
+        let mut contents = String::new();
+        flate2::read::MultiGzDecoder::new(fs::File::open(path).unwrap())
+            .read_to_string(&mut contents)
+            .unwrap();
+        assert_eq!(
+            vec![
+                "time,subj_course_id,sec_code,sec_id,prof,available,waitlist,total,enrolled_ct",
+                "1,CSE 100,A01,1,Prof,0,0,100,100",
+                "2,CSE 100,A01,1,Prof,1,0,100,99",
+            ],
+            contents.lines().collect::<Vec<_>>()
         );
-        // To ensure that login was successful, try to get all courses and ensure those courses
-        // are not empty for all terms.
-        let mut is_successful = true;
-        for term in state.all_terms.keys() {
-            // Wait a few seconds before looping.
-            tokio::time::sleep(Duration::from_secs(GENERAL_DELAY)).await;
-            // Try to associate this term in particular, it's possible that this term might not
-            // be on the list of all terms because it is hidden.
-            if let Err(e) = state.wrapper.associate_term(term).await {
-                num_tries += 1;
-                warn!(
-                    "An error occurred when trying to register term '{term}' ({num_tries}/{MAX_NUM_REGISTER}): '{e}'"
-                );
-                is_successful = false;
-                break;
-            }
 
-            // Wait a few seconds before making another request.
-            tokio::time::sleep(Duration::from_secs(GENERAL_DELAY)).await;
-            let all_courses = match state
-                .wrapper
-                .req(term)
-                .parsed()
-                .search_courses(SearchType::Advanced(SearchRequestBuilder::new()))
-                .await
-            {
-                Ok(o) => {
-                    info!("Found {} courses for the term '{term}'.", o.len());
-                    o
-                }
-                Err(e) => {
-                    num_tries += 1;
-                    warn!("Failed to fetch courses for term '{term}' ({num_tries}/{MAX_NUM_REGISTER}); error received: '{e}'");
-                    is_successful = false;
-                    break;
-                }
-            };
+        fs::remove_dir_all(output_dir).unwrap();
+    }
 
-            if all_courses.is_empty() {
-                is_successful = false;
-                break;
-            }
+    fn make_section(section_id: &str, available_seats: i64) -> CourseSection {
+        CourseSection {
+            subj_course_id: "CSE 100".to_owned(),
+            section_id: section_id.to_owned(),
+            section_code: "A01".to_owned(),
+            all_instructors: vec![],
+            available_seats,
+            enrolled_ct: 100 - available_seats,
+            total_seats: 100,
+            waitlist_ct: 0,
+            meetings: vec![],
+            is_visible: true,
         }
+    }
 
-        if !is_successful {
-            continue;
+    #[test]
+    fn test_changed_sections() {
+        let mut last_seen = HashMap::new();
+        let snapshot = vec![make_section("1", 10), make_section("2", 5)];
+        assert_eq!(2, changed_sections(&mut last_seen, &snapshot).len());
+        assert!(changed_sections(&mut last_seen, &snapshot).is_empty());
+
+        let snapshot = vec![make_section("1", 9), make_section("2", 5)];
+        let changed = changed_sections(&mut last_seen, &snapshot);
+        assert_eq!(1, changed.len());
+        assert_eq!("1", changed[0].section_id);
+    }
+
+    #[tokio::test]
+    async fn test_record_sections_sqlite() {
+        let output_dir =
+            std::env::temp_dir().join(format!("webreg_record_sqlite_{}", std::process::id()));
+        let info = TermInfo {
+            should_save: true,
+            only_on_change: true,
+            sink: OutputSink::Sqlite,
+            output_dir: output_dir.clone(),
+            ..TermInfo::for_test("TEST")
+        };
+        let state = WrapperState::builder().build();
+        let mut sqlite = open_sqlite(&info);
+        assert!(sqlite.is_some());
+        // The tracker doesn't write to a CSV file when saving to SQLite.
+        let mut writer = open_output(&info, NaiveDate::from_ymd_opt(2024, 10, 1).unwrap());
+        assert!(writer.is_none());
+
+        let mut last_seen = HashMap::new();
+        let mut rows = vec![];
+        for (time, snapshot) in [
+            (1, vec![make_section("1", 10), make_section("2", 5)]),
+            // Only the section whose seat counts changed should be recorded.
+            (2, vec![make_section("1", 9), make_section("2", 5)]),
+        ] {
+            record_sections(
+                &info,
+                &mut last_seen,
+                &snapshot,
+                time,
+                writer.as_mut(),
+                Some(&mut rows),
+            );
+            write_rows(&state, &info, sqlite.as_mut(), &mut rows).await;
+            assert!(rows.is_empty());
         }
+        drop(sqlite);
 
-        break;
+        let history = read_history(&info.sqlite_path(), "CSE 100").unwrap();
+        assert_eq!(
+            vec![(1, "1", 10, 90), (1, "2", 5, 95), (2, "1", 9, 91)],
+            history
+                .iter()
+                .map(|e| (e.time, e.sec_id.as_str(), e.available, e.enrolled_ct))
+                .collect::<Vec<_>>()
+        );
+        assert!(history
+            .iter()
+            .all(|e| e.sec_code == "A01" && e.total == 100 && e.waitlist == 0));
+
+        fs::remove_dir_all(output_dir).unwrap();
+    }
+
+    #[tokio::test]
+    async fn test_spaced_requests_overlap() {
+        let in_flight = AtomicUsize::new(0);
+        let max_in_flight = AtomicUsize::new(0);
+        let starts = std::sync::Mutex::new(vec![]);
+        let spacing = || Duration::from_millis(20);
+
+        let start = Instant::now();
+        let finished = spaced_requests(0..6, 3, &spacing, |idx| {
+            let (in_flight, max_in_flight, starts) = (&in_flight, &max_in_flight, &starts);
+            async move {
+                starts.lock().unwrap().push(Instant::now());
+                let n = in_flight.fetch_add(1, Ordering::SeqCst) + 1;
+                max_in_flight.fetch_max(n, Ordering::SeqCst);
+                tokio::time::sleep(Duration::from_millis(100)).await;
+                in_flight.fetch_sub(1, Ordering::SeqCst);
+                idx
+            }
+        })
+        .collect::<Vec<_>>()
+        .await;
+        let elapsed = start.elapsed();
+
+        let mut sorted = finished.clone();
+        sorted.sort();
+        assert_eq!(vec![0, 1, 2, 3, 4, 5], sorted);
+        // The pool should be kept full, rather than going one request at a time.
+        assert_eq!(3, max_in_flight.load(Ordering::SeqCst));
+        assert!(elapsed < Duration::from_millis(6 * 100));
+
+        // Requests should still be started at least `spacing` apart.
+        let starts = starts.into_inner().unwrap();
+        assert_eq!(6, starts.len());
+        for pair in starts.windows(2) {
+            assert!(pair[1] - pair[0] >= Duration::from_millis(20));
+        }
+    }
+
+    #[tokio::test]
+    async fn test_cookie_rotation_waits_for_requests() {
+        let state = Arc::new(WrapperState::builder().build());
+        let registered = Arc::new(AtomicBool::new(false));
+
+        // A request that's in flight with the original cookies.
+        let request = state.cookie_generation.read().await;
+        let rotation = tokio::spawn({
+            let state = state.clone();
+            let registered = registered.clone();
+            async move {
+                let generation = replace_cookies(&state, "new cookies").await;
+                // Registering the new cookies takes a while.
+                tokio::time::sleep(Duration::from_millis(50)).await;
+                registered.store(true, Ordering::SeqCst);
+                drop(generation);
+            }
+        });
+
+        // The cookies shouldn't be replaced while the request is in flight.
+        tokio::time::sleep(Duration::from_millis(20)).await;
+        assert!(!rotation.is_finished());
+        assert_eq!(0, *request);
+        drop(request);
+
+        // Once the request finishes, the cookies are replaced, and the next request has to
+        // wait until they're registered.
+        tokio::time::sleep(Duration::from_millis(10)).await;
+        let request = state.cookie_generation.read().await;
+        assert_eq!(1, *request);
+        assert!(registered.load(Ordering::SeqCst));
+        drop(request);
+
+        rotation.await.unwrap();
+    }
+
+    #[tokio::test]
+    async fn test_wait_while_paused() {
+        let state = Arc::new(WrapperState::builder().build());
+        let info = Arc::new(make_info("FA24", false, ".".into()));
+        let stop_flag = Arc::new(AtomicBool::new(false));
+        assert!(wait_while_paused(&state, &info, &stop_flag).await);
+
+        info.set_paused(true);
+        let waiting = tokio::spawn({
+            let state = state.clone();
+            let info = info.clone();
+            let stop_flag = stop_flag.clone();
+            async move { wait_while_paused(&state, &info, &stop_flag).await }
+        });
+        tokio::time::sleep(PAUSE_POLL_INTERVAL * 2).await;
+        assert!(!waiting.is_finished());
+
+        info.set_paused(false);
+        let resumed = tokio::time::timeout(PAUSE_POLL_INTERVAL * 4, waiting).await;
+        assert!(resumed.unwrap().unwrap());
+    }
+
+    #[tokio::test]
+    async fn test_paused_tracker_stops() {
+        let state = Arc::new(WrapperState::builder().build());
+        let info = make_info("FA24", false, ".".into());
+        info.set_paused(true);
+        let stop_flag = Arc::new(AtomicBool::new(true));
+
+        // A paused tracker shouldn't make any requests, so it should return as soon as
+        // it's told to stop.
+        tokio::time::timeout(
+            PAUSE_POLL_INTERVAL * 4,
+            track_webreg_enrollment(&state, &info, false, stop_flag),
+        )
+        .await
+        .unwrap();
+        assert_eq!(0, info.tracker.num_requests.load(Ordering::SeqCst));
+    }
+
+    #[test]
+    fn test_search_types() {
+        let mut info = make_info("FA24", false, ".".into());
+        info.search_query = vec![SearchRequestBuilder::new(), SearchRequestBuilder::new()];
+        let searches = search_types(&info);
+        assert_eq!(2, searches.len());
+        assert!(searches
+            .iter()
+            .all(|s| matches!(s, SearchType::Advanced(_))));
+
+        // Only the listed sections should be searched for, even if there are search queries.
+        info.watched_sections = vec!["123456".to_owned(), "654321".to_owned()];
+        let searches = search_types(&info);
+        assert_eq!(1, searches.len());
+        assert!(matches!(
+            &searches[0],
+            SearchType::ByMultipleSections(ids) if *ids == info.watched_sections
+        ));
+
+        // The search returns every section of the courses containing the listed sections,
+        // but only the listed sections should be tracked.
+        let results = vec![
+            make_section("123456", 0),
+            make_section("111111", 0),
+            make_section("654321", 0),
+        ];
+        assert_eq!(
+            vec!["123456", "654321"],
+            tracked_sections(&info.watched_sections, results)
+                .iter()
+                .map(|s| s.section_id.as_str())
+                .collect::<Vec<_>>()
+        );
     }
 
-    num_tries < MAX_NUM_REGISTER
+    #[test]
+    fn test_tracked_sections() {
+        let sections = vec![
+            make_section("1", 0),
+            make_section("2", 0),
+            make_section("3", 0),
+        ];
+        assert_eq!(3, tracked_sections(&[], sections.clone()).len());
+
+        let tracked = tracked_sections(&["3".to_owned(), "1".to_owned()], sections);
+        assert_eq!(
+            vec!["1", "3"],
+            tracked
+                .iter()
+                .map(|s| s.section_id.as_str())
+                .collect::<Vec<_>>()
+        );
+    }
 }
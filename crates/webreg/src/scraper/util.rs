@@ -1,4 +1,28 @@
+use std::fmt::Display;
+use std::future::Future;
+use std::time::Duration;
+
 use chrono::format::{DelayedFormat, StrftimeItems};
+use rand::Rng;
+use reqwest::{Client, Response, Url};
+use serde_json::Value;
+use tracing::{info, warn};
+use webweg::util::get_formatted_course_num;
+use webweg::wrapper::input_types::SearchRequestBuilder;
+
+use crate::types::AddressPortInfo;
+
+/// The WebReg endpoint used to search for courses.
+const WEBREG_SEARCH: &str = "https://act.ucsd.edu/webreg2/svc/wradapter/secure/search-by-all";
+/// The WebReg endpoint used to get course information (including enrollment counts).
+const COURSE_DATA: &str =
+    "https://act.ucsd.edu/webreg2/svc/wradapter/secure/search-load-group-data";
+/// The longest that `retry_with_backoff` will wait between two attempts.
+const MAX_BACKOFF_DELAY: Duration = Duration::from_secs(60);
+/// The number of times the cookie servers are asked for session cookies before giving up.
+const COOKIE_FETCH_TRIES: usize = 3;
+/// The base delay between two requests to the cookie servers for session cookies.
+const COOKIE_FETCH_BASE_DELAY: Duration = Duration::from_secs(2);
 
 /// Gets the time in a pretty format. Ideal for logging.
 ///
@@ -22,3 +46,420 @@ pub fn get_pretty_time() -> DelayedFormat<StrftimeItems<'static>> {
 pub fn get_epoch_time() -> i64 {
     chrono::offset::Local::now().timestamp_millis()
 }
+
+/// Builds the WebReg URL that a course search will be made to. This is only meant for logging
+/// purposes, so that a failing request can be reproduced manually. Only the fields that the
+/// tracker uses are considered.
+///
+/// # Parameters
+/// - `query`: The search query.
+/// - `term`: The term.
+///
+/// # Returns
+/// The URL.
+pub fn get_search_url(query: &SearchRequestBuilder, term: &str) -> String {
+    let levels = if query.level_filter == 0 {
+        "".to_string()
+    } else {
+        format!("{:012b}", query.level_filter)
+    };
+
+    Url::parse_with_params(
+        WEBREG_SEARCH,
+        &[
+            ("subjcode", ""),
+            ("crsecode", ""),
+            ("department", query.departments.join(":").as_str()),
+            ("professor", ""),
+            ("title", ""),
+            ("levels", levels.as_str()),
+            ("days", ""),
+            ("timestr", ""),
+            (
+                "opensection",
+                if query.only_open { "true" } else { "false" },
+            ),
+            ("isbasic", "true"),
+            ("basicsearchvalue", ""),
+            ("termcode", term),
+        ],
+    )
+    .map(|url| url.to_string())
+    .unwrap_or_default()
+}
+
+/// Builds the WebReg URL that a request for a course's enrollment count will be made to. This
+/// is only meant for logging purposes, so that a failing request can be reproduced manually.
+///
+/// # Parameters
+/// - `subject_code`: The subject code (e.g., `CSE`).
+/// - `course_num`: The course number (e.g., `100`).
+/// - `term`: The term.
+///
+/// # Returns
+/// The URL.
+pub fn get_course_data_url(subject_code: &str, course_num: &str, term: &str) -> String {
+    Url::parse_with_params(
+        COURSE_DATA,
+        &[
+            ("subjcode", subject_code),
+            ("crsecode", get_formatted_course_num(course_num).as_str()),
+            ("termcode", term),
+        ],
+    )
+    .map(|url| url.to_string())
+    .unwrap_or_default()
+}
+
+/// A response whose body can be read one chunk at a time. Our client and the wrapper's
+/// client are from different versions of `reqwest`, so this lets the responses of both be
+/// read in the same way.
+pub trait ChunkedResponse {
+    /// Gets the length of the body, if the server gave it.
+    ///
+    /// # Returns
+    /// The length of the body, in bytes.
+    fn body_length(&self) -> Option<u64>;
+
+    /// Reads the next chunk of the body.
+    ///
+    /// # Returns
+    /// The next chunk, or `None` if the whole body has been read.
+    async fn next_chunk(&mut self) -> Result<Option<impl AsRef<[u8]>>, String>;
+}
+
+impl ChunkedResponse for Response {
+    fn body_length(&self) -> Option<u64> {
+        self.content_length()
+    }
+
+    async fn next_chunk(&mut self) -> Result<Option<impl AsRef<[u8]>>, String> {
+        self.chunk().await.map_err(|e| e.to_string())
+    }
+}
+
+impl ChunkedResponse for webweg_reqwest::Response {
+    fn body_length(&self) -> Option<u64> {
+        self.content_length()
+    }
+
+    async fn next_chunk(&mut self) -> Result<Option<impl AsRef<[u8]>>, String> {
+        self.chunk().await.map_err(|e| e.to_string())
+    }
+}
+
+/// Reads the body of the given response as text, refusing to read more than the given
+/// number of bytes. This ensures that a single bad response can't exhaust our memory.
+///
+/// # Parameters
+/// - `resp`: The response.
+/// - `max_size`: The maximum number of bytes to read.
+///
+/// # Returns
+/// The response body, or a description of the error if the body couldn't be read or was
+/// too large.
+pub async fn read_text_bounded(
+    mut resp: impl ChunkedResponse,
+    max_size: usize,
+) -> Result<String, String> {
+    if resp.body_length().is_some_and(|len| len > max_size as u64) {
+        return Err(format!(
+            "response body exceeds the maximum size of {max_size} bytes"
+        ));
+    }
+
+    let mut body = vec![];
+    while let Some(chunk) = resp.next_chunk().await? {
+        let chunk = chunk.as_ref();
+        if body.len() + chunk.len() > max_size {
+            return Err(format!(
+                "response body exceeds the maximum size of {max_size} bytes"
+            ));
+        }
+
+        body.extend_from_slice(chunk);
+    }
+
+    String::from_utf8(body).map_err(|e| e.to_string())
+}
+
+/// Requests session cookies from the given cookie servers, trying each one in order until
+/// one of them responds with session cookies.
+///
+/// # Parameters
+/// - `client`: The client to make the requests with.
+/// - `cookie_servers`: The cookie servers.
+/// - `max_body_size`: The maximum size of a response body, in bytes.
+///
+/// # Returns
+/// The index of the cookie server that responded with session cookies, along with the
+/// cookies, or `None` if none of them did.
+pub async fn request_cookies(
+    client: &Client,
+    cookie_servers: &[AddressPortInfo],
+    max_body_size: usize,
+) -> Option<(usize, String)> {
+    for (idx, server) in cookie_servers.iter().enumerate() {
+        let address = format!("{}:{}", server.address, server.port);
+        info!("Making a request to the cookie server (http://{address}/cookie) to get session cookies.");
+        let text = match client.get(format!("http://{address}/cookie")).send().await {
+            Ok(r) => match read_text_bounded(r, max_body_size).await {
+                Ok(o) => o,
+                Err(e) => {
+                    warn!("Unable to read the response from the cookie server ({address}); reason: '{e}'");
+                    continue;
+                }
+            },
+            Err(e) => {
+                warn!("Failed to connect to the cookie server ({address}); reason: '{e}'");
+                continue;
+            }
+        };
+
+        let json: Value = serde_json::from_str(text.as_str()).unwrap_or_default();
+        info!("Received response from cookie server ({address}): '{json}'");
+        match json["cookie"].as_str() {
+            Some(cookies) => return Some((idx, cookies.to_owned())),
+            None => warn!("The 'cookie' key from the response ({address}) is not valid."),
+        }
+    }
+
+    None
+}
+
+/// Requests session cookies from the given cookie servers like `request_cookies` does, but
+/// retries with backoff if none of them respond with session cookies.
+///
+/// # Parameters
+/// - `client`: The client to make the requests with.
+/// - `cookie_servers`: The cookie servers.
+/// - `max_body_size`: The maximum size of a response body, in bytes.
+///
+/// # Returns
+/// The index of the cookie server that responded with session cookies, along with the
+/// cookies, or `None` if none of them did after every attempt.
+pub async fn request_cookies_with_retry(
+    client: &Client,
+    cookie_servers: &[AddressPortInfo],
+    max_body_size: usize,
+) -> Option<(usize, String)> {
+    retry_with_backoff(
+        || async {
+            request_cookies(client, cookie_servers, max_body_size)
+                .await
+                .ok_or("none of the cookie servers provided session cookies")
+        },
+        COOKIE_FETCH_TRIES,
+        COOKIE_FETCH_BASE_DELAY,
+    )
+    .await
+    .ok()
+}
+
+/// Runs the given operation until it succeeds, waiting longer after each failed attempt.
+///
+/// # Parameters
+/// - `op`: The operation.
+/// - `max_tries`: The maximum number of times to run the operation. The operation is
+///   always run at least once.
+/// - `base_delay`: How long to wait after the first failed attempt. This doubles after
+///   every failed attempt, up to a minute, and is randomly shortened by up to half so
+///   that clients don't retry in lockstep.
+///
+/// # Returns
+/// The result of the first successful attempt, or the error from the last attempt if
+/// every attempt failed.
+pub async fn retry_with_backoff<F, Fut, T, E>(
+    mut op: F,
+    max_tries: usize,
+    base_delay: Duration,
+) -> Result<T, E>
+where
+    F: FnMut() -> Fut,
+    Fut: Future<Output = Result<T, E>>,
+    E: Display,
+{
+    let max_tries = max_tries.max(1);
+    let mut num_tries = 0;
+    loop {
+        num_tries += 1;
+        let e = match op().await {
+            Ok(o) => return Ok(o),
+            Err(e) => e,
+        };
+
+        if num_tries >= max_tries {
+            warn!("Attempt {num_tries}/{max_tries} failed, giving up: '{e}'");
+            return Err(e);
+        }
+
+        let delay = backoff_delay(base_delay, num_tries - 1);
+        warn!("Attempt {num_tries}/{max_tries} failed, retrying in {delay:?}: '{e}'");
+        tokio::time::sleep(delay).await;
+    }
+}
+
+/// Computes how long to wait before retrying an operation.
+///
+/// # Parameters
+/// - `base_delay`: How long to wait after the first failed attempt.
+/// - `num_failures`: The number of failed attempts before the last one.
+///
+/// # Returns
+/// A random delay between half of and all of `base_delay * 2^num_failures`, where the
+/// latter is capped at `MAX_BACKOFF_DELAY`.
+fn backoff_delay(base_delay: Duration, num_failures: usize) -> Duration {
+    let factor = 2_u32.saturating_pow(num_failures.try_into().unwrap_or(u32::MAX));
+    let delay = base_delay.saturating_mul(factor).min(MAX_BACKOFF_DELAY);
+    delay.mul_f64(rand::thread_rng().gen_range(0.5..=1.0))
+}
+
+#[cfg(test)]
+mod tests {
+    use axum::routing::get;
+    use axum::Router;
+
+    use super::*;
+
+    /// Starts a cookie server that responds with the given body.
+    async fn start_cookie_server(body: &'static str) -> AddressPortInfo {
+        let router = Router::new().route("/cookie", get(move || async move { body }));
+        let listener = tokio::net::TcpListener::bind("127.0.0.1:0").await.unwrap();
+        let port = listener.local_addr().unwrap().port();
+        tokio::spawn(async move { axum::serve(listener, router).await.unwrap() });
+        AddressPortInfo {
+            address: "127.0.0.1".to_owned(),
+            port: port as i64,
+        }
+    }
+
+    #[tokio::test]
+    async fn test_request_cookies_failover() {
+        // Nothing is listening on this port, so connecting to it fails.
+        let down = {
+            let listener = tokio::net::TcpListener::bind("127.0.0.1:0").await.unwrap();
+            AddressPortInfo {
+                address: "127.0.0.1".to_owned(),
+                port: listener.local_addr().unwrap().port() as i64,
+            }
+        };
+        let invalid = start_cookie_server("{\"error\": \"Not Found\"}").await;
+        let valid = start_cookie_server("{\"cookie\": \"abc=123\"}").await;
+
+        let client = Client::new();
+        let servers = vec![down.clone(), invalid, valid];
+        assert_eq!(
+            Some((2, "abc=123".to_owned())),
+            request_cookies(&client, &servers, 1024).await
+        );
+        assert_eq!(None, request_cookies(&client, &[down], 1024).await);
+    }
+
+    #[tokio::test]
+    async fn test_read_text_bounded() {
+        let router = Router::new()
+            .route("/small", get(|| async { "hello" }))
+            .route("/large", get(|| async { "a".repeat(2048) }))
+            .route(
+                "/endless",
+                get(|| async {
+                    // A body that never ends, and whose length isn't known up front.
+                    axum::body::Body::from_stream(futures::stream::repeat_with(|| {
+                        Ok::<_, std::io::Error>(axum::body::Bytes::from_static(&[b'a'; 256]))
+                    }))
+                }),
+            );
+        let listener = tokio::net::TcpListener::bind("127.0.0.1:0").await.unwrap();
+        let base = format!("http://{}", listener.local_addr().unwrap());
+        tokio::spawn(async move { axum::serve(listener, router).await.unwrap() });
+
+        let client = Client::new();
+        let get = |path: &str| client.get(format!("{base}{path}")).send();
+        assert_eq!(
+            Ok("hello".to_owned()),
+            read_text_bounded(get("/small").await.unwrap(), 1024).await
+        );
+        assert_eq!(
+            Ok("hello".to_owned()),
+            read_text_bounded(get("/small").await.unwrap(), 5).await
+        );
+        assert!(read_text_bounded(get("/large").await.unwrap(), 1024)
+            .await
+            .is_err());
+
+        // Reading should stop as soon as the limit is exceeded, for both clients.
+        let endless = tokio::time::timeout(
+            Duration::from_secs(5),
+            read_text_bounded(get("/endless").await.unwrap(), 1024),
+        );
+        assert!(endless.await.unwrap().is_err());
+
+        let wrapper_client = webweg_reqwest::Client::new();
+        let response = wrapper_client
+            .get(format!("{base}/endless"))
+            .send()
+            .await
+            .unwrap();
+        let endless =
+            tokio::time::timeout(Duration::from_secs(5), read_text_bounded(response, 1024));
+        assert!(endless.await.unwrap().is_err());
+    }
+
+    #[tokio::test]
+    async fn test_retry_with_backoff_succeeds() {
+        let mut num_calls = 0;
+        let result = retry_with_backoff(
+            || {
+                num_calls += 1;
+                let n = num_calls;
+                async move {
+                    if n <= 2 {
+                        Err(format!("failure {n}"))
+                    } else {
+                        Ok(n)
+                    }
+                }
+            },
+            5,
+            Duration::from_millis(1),
+        )
+        .await;
+
+        assert_eq!(Ok(3), result);
+        assert_eq!(3, num_calls);
+    }
+
+    #[tokio::test]
+    async fn test_retry_with_backoff_gives_up() {
+        let mut num_calls = 0;
+        let result: Result<(), _> = retry_with_backoff(
+            || {
+                num_calls += 1;
+                let n = num_calls;
+                async move { Err(format!("failure {n}")) }
+            },
+            4,
+            Duration::from_millis(1),
+        )
+        .await;
+
+        assert_eq!(Err("failure 4".to_owned()), result);
+        assert_eq!(4, num_calls);
+    }
+
+    #[test]
+    fn test_backoff_delay() {
+        let base = Duration::from_secs(2);
+        for num_failures in 0..4 {
+            let max = base * 2_u32.pow(num_failures as u32);
+            let delay = backoff_delay(base, num_failures);
+            assert!(
+                delay >= max / 2 && delay <= max,
+                "{delay:?} not in [{max:?}/2, {max:?}]"
+            );
+        }
+
+        let delay = backoff_delay(base, 100);
+        assert!(delay >= MAX_BACKOFF_DELAY / 2 && delay <= MAX_BACKOFF_DELAY);
+    }
+}
@@ -0,0 +1,145 @@
+use std::collections::HashMap;
+use std::time::Duration;
+
+use reqwest::header::CONTENT_TYPE;
+use reqwest::Client;
+use serde_json::json;
+use tracing::{info, warn};
+use webweg::types::CourseSection;
+
+/// How long a request to the webhook can take before it's abandoned.
+const WEBHOOK_TIMEOUT: Duration = Duration::from_secs(10);
+
+/// Finds the watched sections that had no available seats when they were last seen, but
+/// now have at least one.
+///
+/// # Parameters
+/// - `last_available`: The number of available seats of every watched section as of when
+///   it was last seen. This is updated with the given sections.
+/// - `watched`: The IDs of the sections that are being watched.
+/// - `sections`: The sections.
+///
+/// # Returns
+/// The sections that just opened a seat. Sections that haven't been seen before are never
+/// included, since there's nothing to compare them to.
+pub fn opened_sections<'a>(
+    last_available: &mut HashMap<String, i64>,
+    watched: &[String],
+    sections: &'a [CourseSection],
+) -> Vec<&'a CourseSection> {
+    sections
+        .iter()
+        .filter(|section| watched.contains(&section.section_id))
+        .filter(|section| {
+            let previous =
+                last_available.insert(section.section_id.clone(), section.available_seats);
+            previous == Some(0) && section.available_seats > 0
+        })
+        .collect()
+}
+
+/// Notifies the webhook that a section has opened a seat. Any errors are logged.
+///
+/// # Parameters
+/// - `client`: The client to make the request with.
+/// - `url`: The webhook's URL.
+/// - `term`: The term that the section is in.
+/// - `section`: The section that opened a seat.
+pub async fn notify_seat_opened(client: Client, url: String, term: String, section: CourseSection) {
+    let body = json!({
+        "term": term,
+        "course": section.subj_course_id,
+        "section": section.section_code,
+        "sectionId": section.section_id,
+        "seats": section.available_seats,
+    });
+
+    match client
+        .post(url.as_str())
+        .header(CONTENT_TYPE, "application/json")
+        .body(body.to_string())
+        .timeout(WEBHOOK_TIMEOUT)
+        .send()
+        .await
+        .and_then(|resp| resp.error_for_status())
+    {
+        Ok(_) => info!(
+            "[{term}] Notified the webhook that section {} ({}) opened a seat.",
+            section.section_id, section.subj_course_id
+        ),
+        Err(e) => warn!(
+            "[{term}] Unable to notify the webhook that section {} opened a seat: '{e}'",
+            section.section_id
+        ),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::sync::{Arc, Mutex};
+
+    use axum::routing::post;
+    use axum::{Json, Router};
+    use serde_json::Value;
+
+    use super::*;
+
+    fn make_section(section_id: &str, available_seats: i64) -> CourseSection {
+        CourseSection {
+            subj_course_id: "CSE 100".to_owned(),
+            section_id: section_id.to_owned(),
+            section_code: "A01".to_owned(),
+            all_instructors: vec![],
+            available_seats,
+            enrolled_ct: 100 - available_seats,
+            total_seats: 100,
+            waitlist_ct: 0,
+            meetings: vec![],
+            is_visible: true,
+        }
+    }
+
+    #[tokio::test]
+    async fn test_notify_on_seat_opened() {
+        let received = Arc::new(Mutex::new(vec![]));
+        let router = Router::new().route(
+            "/",
+            post({
+                let received = received.clone();
+                move |Json(body): Json<Value>| async move {
+                    received.lock().unwrap().push(body);
+                }
+            }),
+        );
+        let listener = tokio::net::TcpListener::bind("127.0.0.1:0").await.unwrap();
+        let url = format!("http://{}/", listener.local_addr().unwrap());
+        tokio::spawn(async move { axum::serve(listener, router).await.unwrap() });
+
+        let watched = vec!["1".to_owned()];
+        let mut last_available = HashMap::new();
+        for seats in [0, 0, 1, 2, 0] {
+            // Section 2 isn't watched, so it should never cause a notification.
+            let snapshot = vec![make_section("1", seats), make_section("2", seats)];
+            for section in opened_sections(&mut last_available, &watched, &snapshot) {
+                notify_seat_opened(
+                    Client::new(),
+                    url.clone(),
+                    "FA24".to_owned(),
+                    section.clone(),
+                )
+                .await;
+            }
+        }
+
+        assert_eq!(
+            vec![json!({
+                "term": "FA24",
+                "course": "CSE 100",
+                "section": "A01",
+                "sectionId": "1",
+                "seats": 1,
+            })],
+            *received.lock().unwrap()
+        );
+    }
+}
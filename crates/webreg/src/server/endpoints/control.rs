@@ -0,0 +1,120 @@
+use std::sync::Arc;
+
+use axum::extract::{Path, State};
+use axum::http::StatusCode;
+use axum::response::{IntoResponse, Response};
+use axum::Json;
+use serde_json::json;
+use tracing::log::info;
+
+use crate::types::WrapperState;
+
+/// A function which should be called when the `control/:term/pause` endpoint is called.
+/// This pauses the tracker for the term, without affecting any other term.
+#[tracing::instrument(level = "info", skip(s))]
+pub async fn post_pause_term(
+    Path(term): Path<String>,
+    State(s): State<Arc<WrapperState>>,
+) -> Response {
+    info!("POST endpoint `control/:term/pause` called");
+    set_paused(&s, term.as_str(), true)
+}
+
+/// A function which should be called when the `control/:term/resume` endpoint is called.
+/// This resumes the tracker for the term, if it was paused.
+#[tracing::instrument(level = "info", skip(s))]
+pub async fn post_resume_term(
+    Path(term): Path<String>,
+    State(s): State<Arc<WrapperState>>,
+) -> Response {
+    info!("POST endpoint `control/:term/resume` called");
+    set_paused(&s, term.as_str(), false)
+}
+
+/// Pauses or resumes the tracker for a term.
+///
+/// # Parameters
+/// - `s`: The wrapper state.
+/// - `term`: The term.
+/// - `paused`: Whether the tracker should be paused.
+///
+/// # Returns
+/// The response, which includes whether the tracker is now paused.
+fn set_paused(s: &WrapperState, term: &str, paused: bool) -> Response {
+    let term = term.to_uppercase();
+    let Some(info) = s.term(term.as_str()) else {
+        return StatusCode::NOT_FOUND.into_response();
+    };
+
+    info.set_paused(paused);
+    info!("[{term}] The tracker's paused state was set to: {paused}");
+    (
+        StatusCode::OK,
+        Json(json!({ "term": term, "paused": paused })),
+    )
+        .into_response()
+}
+
+#[cfg(test)]
+mod tests {
+    use axum::body::{to_bytes, Body};
+    use axum::http::{Method, Request};
+    use serde_json::Value;
+    use tower::ServiceExt;
+
+    use super::*;
+    use crate::server::create_router;
+    use crate::types::TermInfo;
+
+    #[tokio::test]
+    async fn test_pause_and_resume() {
+        let state = Arc::new(
+            WrapperState::builder()
+                .with_terms(vec![TermInfo::for_test("FA24"), TermInfo::for_test("WI25")])
+                .build(),
+        );
+
+        let response = post_pause_term(Path("fa24".to_owned()), State(state.clone())).await;
+        assert_eq!(StatusCode::OK, response.status());
+        let body = to_bytes(response.into_body(), usize::MAX).await.unwrap();
+        let body: Value = serde_json::from_slice(&body).unwrap();
+        assert_eq!(json!({ "term": "FA24", "paused": true }), body);
+        assert!(state.term("FA24").unwrap().is_paused());
+        // Other terms aren't affected.
+        assert!(!state.term("WI25").unwrap().is_paused());
+
+        let response = post_resume_term(Path("FA24".to_owned()), State(state.clone())).await;
+        assert_eq!(StatusCode::OK, response.status());
+        assert!(!state.term("FA24").unwrap().is_paused());
+
+        let response = post_pause_term(Path("SP25".to_owned()), State(state)).await;
+        assert_eq!(StatusCode::NOT_FOUND, response.status());
+    }
+
+    #[tokio::test]
+    async fn test_control_route() {
+        let state = Arc::new(
+            WrapperState::builder()
+                .with_terms(vec![TermInfo::for_test("FA24")])
+                .build(),
+        );
+
+        let response = create_router(state.clone())
+            .oneshot(
+                Request::builder()
+                    .method(Method::POST)
+                    .uri("/control/FA24/pause")
+                    .body(Body::empty())
+                    .unwrap(),
+            )
+            .await
+            .unwrap();
+        if cfg!(feature = "auth") {
+            assert_eq!(StatusCode::UNAUTHORIZED, response.status());
+            assert!(!state.term("FA24").unwrap().is_paused());
+        } else {
+            assert_eq!(StatusCode::OK, response.status());
+            assert!(state.term("FA24").unwrap().is_paused());
+        }
+    }
+}
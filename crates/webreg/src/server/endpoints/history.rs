@@ -0,0 +1,161 @@
+use std::sync::Arc;
+
+use axum::extract::{Path, Query, State};
+use axum::http::StatusCode;
+use axum::response::{IntoResponse, Response};
+use axum::Json;
+use tracing::log::info;
+
+use crate::scraper::sqlite::read_history;
+use crate::server::types::{ApiErrorType, CourseQueryStr};
+use crate::types::{OutputSink, WrapperState};
+
+/// A function which should be called when the `history/:term` endpoint is called. This
+/// reads the enrollment history of a course from the term's SQLite database.
+#[tracing::instrument(level = "info", skip(s))]
+pub async fn get_history(
+    Path(term): Path<String>,
+    Query(crsc): Query<CourseQueryStr>,
+    State(s): State<Arc<WrapperState>>,
+) -> Response {
+    info!("GET endpoint `history` called");
+    let Some(info) = s.term(term.to_uppercase().as_str()) else {
+        return StatusCode::NOT_FOUND.into_response();
+    };
+
+    if !info.should_save || info.sink != OutputSink::Sqlite {
+        return ApiErrorType::from((
+            StatusCode::NOT_FOUND,
+            "The enrollment history of this term isn't saved to a SQLite database.",
+            Some(info.term.clone()),
+        ))
+        .into_response();
+    }
+
+    let course = format!(
+        "{} {}",
+        crsc.subject.trim().to_uppercase(),
+        crsc.number.trim().to_uppercase()
+    );
+    let path = info.sqlite_path();
+    let history = tokio::task::spawn_blocking(move || read_history(&path, course.as_str()))
+        .await
+        .map_err(|e| e.to_string())
+        .and_then(|result| result.map_err(|e| e.to_string()));
+
+    match history {
+        Ok(history) => (StatusCode::OK, Json(history)).into_response(),
+        Err(e) => ApiErrorType::from((
+            StatusCode::INTERNAL_SERVER_ERROR,
+            "The enrollment history couldn't be read.",
+            Some(e),
+        ))
+        .into_response(),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use axum::body::to_bytes;
+    use serde_json::{json, Value};
+
+    use super::*;
+    use crate::scraper::row::EnrollmentRow;
+    use crate::scraper::sqlite::SqliteSink;
+    use crate::types::TermInfo;
+
+    fn make_term_info(term: &str, sink: OutputSink, output_dir: &std::path::Path) -> TermInfo {
+        TermInfo {
+            should_save: true,
+            sink,
+            output_dir: output_dir.to_path_buf(),
+            ..TermInfo::for_test(term)
+        }
+    }
+
+    fn make_row(time: i64, subj_course_id: &str, available: i64) -> EnrollmentRow {
+        EnrollmentRow {
+            time,
+            subj_course_id: subj_course_id.to_owned(),
+            sec_code: "A01".to_owned(),
+            sec_id: "123456".to_owned(),
+            prof: "Doe, John".to_owned(),
+            available,
+            waitlist: 1,
+            total: 100,
+            enrolled_ct: 100 - available,
+        }
+    }
+
+    #[tokio::test]
+    async fn test_get_history() {
+        let output_dir =
+            std::env::temp_dir().join(format!("webreg_history_{}", std::process::id()));
+        std::fs::create_dir_all(&output_dir).unwrap();
+        let state = Arc::new(
+            WrapperState::builder()
+                .with_terms(vec![
+                    make_term_info("FA24", OutputSink::Sqlite, &output_dir),
+                    make_term_info("WI25", OutputSink::Csv, &output_dir),
+                ])
+                .build(),
+        );
+
+        let info = state.term("FA24").unwrap();
+        let mut sink = SqliteSink::open(&info.sqlite_path()).unwrap();
+        sink.insert(
+            "FA24",
+            &[
+                make_row(20, "CSE 100", 9),
+                make_row(10, "CSE 100", 10),
+                make_row(15, "CSE 101", 50),
+                make_row(30, "CSE 100", 8),
+            ],
+        );
+        drop(sink);
+
+        let query = |subject: &str, number: &str| {
+            Query(CourseQueryStr {
+                subject: subject.to_owned(),
+                number: number.to_owned(),
+            })
+        };
+
+        let response = get_history(
+            Path("fa24".to_owned()),
+            query("cse", "100"),
+            State(state.clone()),
+        )
+        .await;
+        assert_eq!(StatusCode::OK, response.status());
+        let body = to_bytes(response.into_body(), usize::MAX).await.unwrap();
+        let body: Value = serde_json::from_slice(&body).unwrap();
+        let entry = |time: i64, available: i64| {
+            json!({
+                "time": time,
+                "sec_code": "A01",
+                "sec_id": "123456",
+                "available": available,
+                "waitlist": 1,
+                "total": 100,
+                "enrolled_ct": 100 - available,
+            })
+        };
+        assert_eq!(json!([entry(10, 10), entry(20, 9), entry(30, 8)]), body);
+
+        // The history of a term that isn't saved to SQLite can't be read.
+        let response = get_history(
+            Path("WI25".to_owned()),
+            query("CSE", "100"),
+            State(state.clone()),
+        )
+        .await;
+        assert_eq!(StatusCode::NOT_FOUND, response.status());
+
+        let response =
+            get_history(Path("SP25".to_owned()), query("CSE", "100"), State(state)).await;
+        assert_eq!(StatusCode::NOT_FOUND, response.status());
+
+        std::fs::remove_dir_all(output_dir).unwrap();
+    }
+}
@@ -1,3 +1,7 @@
+pub mod control;
+pub mod history;
+#[cfg(feature = "auth")]
+pub mod session;
 pub mod status;
 pub mod ww_cookies;
 pub mod ww_general;
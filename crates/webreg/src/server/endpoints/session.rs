@@ -0,0 +1,25 @@
+use std::sync::Arc;
+
+use axum::extract::State;
+use axum::http::StatusCode;
+use axum::response::{IntoResponse, Response};
+use axum::Json;
+use serde_json::json;
+use tracing::log::info;
+
+use crate::scraper::tracker::login_with_cookies;
+use crate::server::types::BodyCookies;
+use crate::types::WrapperState;
+
+/// A function which should be called when the `session/cookies` endpoint is called. This
+/// replaces the session cookies used by the scraper with the given cookies.
+#[tracing::instrument(level = "info", skip(s, body))]
+pub async fn post_session_cookies(
+    State(s): State<Arc<WrapperState>>,
+    Json(body): Json<BodyCookies>,
+) -> Response {
+    info!("POST endpoint `session/cookies` called");
+    let success = login_with_cookies(&s, body.cookie.as_str()).await;
+    info!("Session cookies were replaced; validated: {success}");
+    (StatusCode::OK, Json(json!({ "success": success }))).into_response()
+}
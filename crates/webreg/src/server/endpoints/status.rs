@@ -1,21 +1,83 @@
+use std::fmt::Write;
 use std::sync::atomic::Ordering;
 use std::sync::Arc;
 
 use axum::extract::{Path, State};
-use axum::http::StatusCode;
+use axum::http::{header, StatusCode, Uri};
 use axum::response::{IntoResponse, Response};
 use axum::Json;
-use serde_json::{json, Value};
-use tracing::log::info;
+use serde_json::json;
+use tracing::log::{info, warn};
 
-use crate::types::WrapperState;
+use crate::scraper::util::read_text_bounded;
+use crate::server::types::LoginStat;
+use crate::types::{TermInfo, WrapperMap, WrapperState, SCHEMA_VERSION};
+
+/// The OpenAPI document describing all routes. This should be kept in sync with
+/// `create_router`.
+const OPENAPI_SPEC: &str = include_str!("../../../openapi.json");
+
+/// All top-level routes. This should be kept in sync with `create_router`.
+const TOP_LEVEL_ROUTES: &[&str] = &[
+    "/health",
+    "/openapi.json",
+    "/terms",
+    "/timing/:term",
+    "/history/:term",
+    "/metrics",
+    "/login_stat/:stat",
+    "/control/:term/pause",
+    "/control/:term/resume",
+    #[cfg(feature = "auth")]
+    "/session/cookies",
+];
+
+/// All routes under `/live/:term`. This should be kept in sync with `create_router`.
+const LIVE_ROUTES: &[&str] = &[
+    "/course_info",
+    "/course_info_csv",
+    "/course_info_batch",
+    "/enrollment",
+    "/prerequisites",
+    "/search",
+    "/department_codes",
+    "/subject_codes",
+    "/course_text",
+    "/section_text",
+    "/section/:id",
+    "/add_section",
+    "/validate_add_section",
+    "/drop_section",
+    "/add_plan",
+    "/validate_add_plan",
+    "/remove_plan",
+    "/whoami",
+    "/schedule",
+    "/schedule_ics",
+    "/schedule_list",
+    "/waitlist",
+    "/register_term",
+    "/events",
+    "/rename_schedule",
+];
 
 /// A function to be executed when the `health` endpoint is called.
 #[tracing::instrument(skip(s))]
 pub async fn get_health(State(s): State<Arc<WrapperState>>) -> Response {
     info!("Called `health` endpoint.");
     let status = s.is_running();
-    let response = json!({ "api": status });
+    let mut paused = s
+        .terms()
+        .into_values()
+        .filter(|info| info.is_paused())
+        .map(|info| info.term.clone())
+        .collect::<Vec<_>>();
+    paused.sort();
+    let response = json!({
+        "api": status,
+        "paused": paused,
+        "schema_version": SCHEMA_VERSION
+    });
 
     info!("Returned status: {status}");
     (StatusCode::OK, Json(response)).into_response()
@@ -28,18 +90,31 @@ pub async fn get_timing_stats(
     State(s): State<Arc<WrapperState>>,
 ) -> Response {
     info!("Called with path '{term}'.");
-    if let Some(t) = s.all_terms.get(term.as_str()) {
+    if let Some(t) = s.term(term.as_str()) {
         let num_requests = t.tracker.num_requests.load(Ordering::SeqCst);
         let time_spent = t.tracker.total_time_spent.load(Ordering::SeqCst);
         let recent_requests = {
             let temp = t.tracker.recent_requests.lock().unwrap();
             temp.iter().copied().collect::<Vec<_>>()
         };
+        let errors = t.tracker.error_counts.lock().unwrap().clone();
+
+        let mut sorted = recent_requests.clone();
+        sorted.sort_unstable();
+        let avg_ms =
+            (!sorted.is_empty()).then(|| sorted.iter().sum::<usize>() as f64 / sorted.len() as f64);
 
         let json = json!({
             "ttl_requests": num_requests,
             "ttl_time_ms": time_spent,
-            "recent_requests": recent_requests
+            "recent_requests": recent_requests,
+            "avg_ms": avg_ms,
+            "p50_ms": percentile(&sorted, 50.0),
+            "p95_ms": percentile(&sorted, 95.0),
+            "p99_ms": percentile(&sorted, 99.0),
+            "errors": errors,
+            "cooldown": t.cooldown,
+            "current_cooldown": t.cooldown()
         });
 
         (StatusCode::OK, Json(json)).into_response()
@@ -66,42 +141,369 @@ pub async fn get_login_script_stats(
             .into_response();
     }
 
-    let cookie_url = format!(
-        "http://{}:{}/{}",
-        s.cookie_server.address, s.cookie_server.port, stat_type
-    );
-
-    match s.client.get(cookie_url).send().await {
-        Ok(r) => {
-            let resp = r.text().await.unwrap_or_else(|_| {
-                match stat_type.as_str() {
-                    "start" => "0",
-                    "history" => "[]",
-                    _ => "{}",
-                }
-                .to_string()
-            });
-
-            // resp is a String, so if we were to just return Json(resp),
-            // then we will end up with a string response even though we want
-            // to return a JSON object. So, convert to Value first and *then*
-            // return that as JSON.
-            match serde_json::from_str::<Value>(resp.as_str()) {
-                Ok(o) => (StatusCode::OK, Json(o)).into_response(),
-                Err(e) => {
-                    let err = json!({
-                        "error": e.to_string()
-                    });
-                    (StatusCode::INTERNAL_SERVER_ERROR, Json(err)).into_response()
-                }
+    let text = match s.cookie_server() {
+        Some(server) => {
+            let cookie_url = format!("http://{}:{}/{}", server.address, server.port, stat_type);
+            match s.client.get(cookie_url).send().await {
+                Ok(r) => read_text_bounded(r, s.max_body_size).await,
+                Err(e) => Err(e.to_string()),
             }
         }
+        None => Err("no cookie server is configured".to_owned()),
+    };
+
+    // The login script is upstream of this server, so anything wrong with its response is
+    // reported as a bad gateway rather than being passed through.
+    match text.and_then(|text| LoginStat::parse(stat_type.as_str(), text.as_str())) {
+        Ok(o) => (StatusCode::OK, Json(o)).into_response(),
         Err(e) => {
+            warn!("Unable to get the '{stat_type}' statistic from the login script: '{e}'");
             let json = json!({
-                "error": e.to_string()
+                "error": "The login script returned an invalid response.",
+                "context": e
             });
 
-            (StatusCode::INTERNAL_SERVER_ERROR, Json(json)).into_response()
+            (StatusCode::BAD_GATEWAY, Json(json)).into_response()
+        }
+    }
+}
+
+/// Gets the given percentile of the values using the nearest-rank method.
+///
+/// # Parameters
+/// - `sorted`: The values, sorted in ascending order.
+/// - `p`: The percentile, between `0` and `100`.
+///
+/// # Returns
+/// The smallest value that at least `p` percent of the values are less than or equal to,
+/// or `None` if there are no values.
+fn percentile(sorted: &[usize], p: f64) -> Option<usize> {
+    let rank = (p / 100.0 * sorted.len() as f64).ceil() as usize;
+    sorted.get(rank.clamp(1, sorted.len().max(1)) - 1).copied()
+}
+
+/// A function to be executed when the `metrics` endpoint is called. This reports every
+/// term's timing stats in Prometheus' text format.
+#[tracing::instrument(skip(s))]
+pub async fn get_metrics(State(s): State<Arc<WrapperState>>) -> Response {
+    info!("GET endpoint `metrics` called");
+    (
+        StatusCode::OK,
+        [(header::CONTENT_TYPE, "text/plain; version=0.0.4")],
+        render_metrics(&s.terms()),
+    )
+        .into_response()
+}
+
+/// A metric reported by the `metrics` endpoint.
+struct Metric {
+    /// The metric's name.
+    name: &'static str,
+    /// The metric's type, e.g., `counter`.
+    kind: &'static str,
+    /// A description of the metric.
+    help: &'static str,
+    /// Gets the metric's value for a term, if it has one.
+    value: fn(&TermInfo) -> Option<f64>,
+}
+
+/// Renders every term's timing stats in Prometheus' text format.
+///
+/// # Parameters
+/// - `terms`: The terms.
+///
+/// # Returns
+/// The metrics, with one sample per term for each metric. Terms are ordered by name.
+fn render_metrics(terms: &WrapperMap) -> String {
+    let mut terms = terms.values().collect::<Vec<_>>();
+    terms.sort_by(|a, b| a.term.cmp(&b.term));
+
+    let metrics = [
+        Metric {
+            name: "webreg_requests_total",
+            kind: "counter",
+            help: "The number of requests made to WebReg.",
+            value: |t| Some(t.tracker.num_requests.load(Ordering::SeqCst) as f64),
+        },
+        Metric {
+            name: "webreg_request_time_milliseconds_total",
+            kind: "counter",
+            help: "The total amount of time spent making requests to WebReg.",
+            value: |t| Some(t.tracker.total_time_spent.load(Ordering::SeqCst) as f64),
+        },
+        Metric {
+            name: "webreg_recent_request_duration_milliseconds",
+            kind: "gauge",
+            help: "The average duration of the most recent requests made to WebReg.",
+            value: |t| {
+                let recent = t.tracker.recent_requests.lock().unwrap();
+                (!recent.is_empty())
+                    .then(|| recent.iter().sum::<usize>() as f64 / recent.len() as f64)
+            },
+        },
+        Metric {
+            name: "webreg_cooldown_seconds",
+            kind: "gauge",
+            help: "The cooldown currently being used between requests.",
+            value: |t| Some(t.cooldown()),
+        },
+    ];
+
+    let mut out = String::new();
+    for Metric {
+        name,
+        kind,
+        help,
+        value,
+    } in metrics
+    {
+        let _ = writeln!(out, "# HELP {name} {help}");
+        let _ = writeln!(out, "# TYPE {name} {kind}");
+        for term in &terms {
+            // A term without any recent requests has no meaningful average, so it's left
+            // out rather than reported as zero.
+            if let Some(v) = value(term) {
+                let _ = writeln!(out, "{name}{{term=\"{}\"}} {v}", term.term);
+            }
+        }
+    }
+
+    out
+}
+
+/// A function to be executed when the `openapi.json` endpoint is called.
+#[tracing::instrument]
+pub async fn get_openapi() -> Response {
+    info!("Called `openapi.json` endpoint.");
+    (
+        StatusCode::OK,
+        [(header::CONTENT_TYPE, "application/json")],
+        OPENAPI_SPEC,
+    )
+        .into_response()
+}
+
+/// A function to be executed when no other route matches the request. This lists all
+/// available routes so that a mistyped path is easier to fix.
+#[tracing::instrument]
+pub async fn get_fallback(uri: Uri) -> Response {
+    info!("No endpoint matched path '{}'.", uri.path());
+    let response = json!({
+        "error": "The specified endpoint cannot be found",
+        "routes": TOP_LEVEL_ROUTES,
+        "live_routes": LIVE_ROUTES,
+        "hint": "Routes in `live_routes` should be prefixed with `/live/:term`, e.g., `/live/FA24/search`."
+    });
+
+    (StatusCode::NOT_FOUND, Json(response)).into_response()
+}
+
+#[cfg(test)]
+mod tests {
+    use std::sync::Arc;
+
+    use axum::body::to_bytes;
+    use axum::extract::{Path, State};
+    use axum::http::StatusCode;
+    use axum::routing::get;
+    use axum::Router;
+    use serde_json::{json, Value};
+
+    use super::{
+        get_health, get_login_script_stats, get_timing_stats, percentile, render_metrics,
+        LIVE_ROUTES, OPENAPI_SPEC, TOP_LEVEL_ROUTES,
+    };
+    use crate::server::types::{LoginHistoryEntry, LoginStart, LoginStat};
+    use crate::types::{AddressPortInfo, TermInfo, WrapperMap, WrapperState, SCHEMA_VERSION};
+
+    /// Converts a route from `axum`'s syntax (`/timing/:term`) to OpenAPI's syntax
+    /// (`/timing/{term}`).
+    fn to_openapi_path(route: &str) -> String {
+        route
+            .split('/')
+            .map(|part| match part.strip_prefix(':') {
+                Some(param) => format!("{{{param}}}"),
+                None => part.to_owned(),
+            })
+            .collect::<Vec<_>>()
+            .join("/")
+    }
+
+    #[test]
+    fn test_openapi_covers_routes() {
+        let spec: Value = serde_json::from_str(OPENAPI_SPEC).unwrap();
+        let paths = spec["paths"].as_object().unwrap();
+        let routes = TOP_LEVEL_ROUTES
+            .iter()
+            .map(|route| to_openapi_path(route))
+            .chain(
+                LIVE_ROUTES
+                    .iter()
+                    .map(|route| format!("/live/{{term}}{}", to_openapi_path(route))),
+            );
+
+        for route in routes {
+            assert!(paths.contains_key(&route), "'{route}' is not documented");
+        }
+    }
+
+    #[tokio::test]
+    async fn test_health() {
+        let state = Arc::new(WrapperState::builder().build());
+        let response = get_health(State(state)).await;
+        assert_eq!(StatusCode::OK, response.status());
+
+        let body = to_bytes(response.into_body(), usize::MAX).await.unwrap();
+        let body: Value = serde_json::from_slice(&body).unwrap();
+        assert_eq!(
+            json!({ "api": false, "paused": [], "schema_version": SCHEMA_VERSION }),
+            body
+        );
+    }
+
+    #[tokio::test]
+    async fn test_health_paused() {
+        let state = Arc::new(
+            WrapperState::builder()
+                .with_terms(vec![
+                    TermInfo::for_test("WI25"),
+                    TermInfo::for_test("FA24"),
+                    TermInfo::for_test("SP25"),
+                ])
+                .build(),
+        );
+        state.term("WI25").unwrap().set_paused(true);
+        state.term("FA24").unwrap().set_paused(true);
+
+        let response = get_health(State(state)).await;
+        let body = to_bytes(response.into_body(), usize::MAX).await.unwrap();
+        let body: Value = serde_json::from_slice(&body).unwrap();
+        assert_eq!(json!(["FA24", "WI25"]), body["paused"]);
+    }
+
+    #[test]
+    fn test_parse_login_stat() {
+        assert_eq!(
+            Ok(LoginStat::Start(LoginStart {
+                epoch: 1700000000000
+            })),
+            LoginStat::parse("start", "1700000000000")
+        );
+        assert_eq!(
+            Ok(LoginStat::History(vec![LoginHistoryEntry {
+                timestamp: 1700000000000,
+                success: true,
+            }])),
+            LoginStat::parse("history", "[1700000000000]")
+        );
+
+        assert!(LoginStat::parse("start", "{\"error\": \"Not Found\"}").is_err());
+        assert!(LoginStat::parse("history", "[\"yesterday\"]").is_err());
+        assert!(LoginStat::parse("history", "").is_err());
+    }
+
+    #[tokio::test]
+    async fn test_login_script_stats() {
+        // The login script's start time is well-formed, but its history isn't.
+        let router = Router::new()
+            .route("/start", get(|| async { "1700000000000" }))
+            .route("/history", get(|| async { "[1700000000000, null]" }));
+        let listener = tokio::net::TcpListener::bind("127.0.0.1:0").await.unwrap();
+        let port = listener.local_addr().unwrap().port();
+        tokio::spawn(async move { axum::serve(listener, router).await.unwrap() });
+
+        let state = Arc::new(
+            WrapperState::builder()
+                .with_cookie_servers(vec![AddressPortInfo {
+                    address: "127.0.0.1".to_owned(),
+                    port: port as i64,
+                }])
+                .build(),
+        );
+
+        let response = get_login_script_stats(Path("start".to_owned()), State(state.clone())).await;
+        assert_eq!(StatusCode::OK, response.status());
+        let body = to_bytes(response.into_body(), usize::MAX).await.unwrap();
+        let body: Value = serde_json::from_slice(&body).unwrap();
+        assert_eq!(json!({ "epoch": 1700000000000_i64 }), body);
+
+        let response = get_login_script_stats(Path("history".to_owned()), State(state)).await;
+        assert_eq!(StatusCode::BAD_GATEWAY, response.status());
+    }
+
+    fn make_term(term: &str) -> (String, Arc<TermInfo>) {
+        (term.to_owned(), Arc::new(TermInfo::for_test(term)))
+    }
+
+    #[test]
+    fn test_render_metrics() {
+        let terms: WrapperMap = [make_term("WI25"), make_term("FA24")].into_iter().collect();
+        terms["FA24"].tracker.add_stat(100);
+        terms["FA24"].tracker.add_stat(300);
+
+        let metrics = render_metrics(&terms);
+        for line in [
+            "# TYPE webreg_requests_total counter",
+            "webreg_requests_total{term=\"FA24\"} 2",
+            "webreg_requests_total{term=\"WI25\"} 0",
+            "# TYPE webreg_request_time_milliseconds_total counter",
+            "webreg_request_time_milliseconds_total{term=\"FA24\"} 400",
+            "# TYPE webreg_recent_request_duration_milliseconds gauge",
+            "webreg_recent_request_duration_milliseconds{term=\"FA24\"} 200",
+            "webreg_cooldown_seconds{term=\"WI25\"} 1.5",
+        ] {
+            assert!(metrics.lines().any(|l| l == line), "missing '{line}'");
+        }
+
+        // WI25 hasn't made any requests, so it has no average duration.
+        assert!(!metrics.contains("webreg_recent_request_duration_milliseconds{term=\"WI25\"}"));
+        // Terms should be ordered by name.
+        assert!(
+            metrics.find("webreg_requests_total{term=\"FA24\"}")
+                < metrics.find("webreg_requests_total{term=\"WI25\"}")
+        );
+    }
+
+    #[test]
+    fn test_percentile() {
+        let values = (1..=100).collect::<Vec<_>>();
+        assert_eq!(Some(50), percentile(&values, 50.0));
+        assert_eq!(Some(95), percentile(&values, 95.0));
+        assert_eq!(Some(99), percentile(&values, 99.0));
+        assert_eq!(Some(1), percentile(&values, 0.0));
+        assert_eq!(Some(7), percentile(&[7], 99.0));
+        assert_eq!(None, percentile(&[], 50.0));
+    }
+
+    #[tokio::test]
+    async fn test_timing_stats_percentiles() {
+        let state = Arc::new(
+            WrapperState::builder()
+                .with_terms(vec![TermInfo::for_test("FA24"), TermInfo::for_test("WI25")])
+                .build(),
+        );
+        // Add the stats out of order, since they're recorded in the order they finish.
+        for time in (1..=100).rev() {
+            state.term("FA24").unwrap().tracker.add_stat(time * 10);
+        }
+        state.term("FA24").unwrap().tracker.add_error("status_429");
+        state.term("FA24").unwrap().tracker.add_error("status_429");
+
+        let response = get_timing_stats(Path("FA24".to_owned()), State(state.clone())).await;
+        let body = to_bytes(response.into_body(), usize::MAX).await.unwrap();
+        let body: Value = serde_json::from_slice(&body).unwrap();
+        assert_eq!(json!(505.0), body["avg_ms"]);
+        assert_eq!(json!(500), body["p50_ms"]);
+        assert_eq!(json!(950), body["p95_ms"]);
+        assert_eq!(json!(990), body["p99_ms"]);
+        assert_eq!(json!({ "status_429": 2 }), body["errors"]);
+
+        let response = get_timing_stats(Path("WI25".to_owned()), State(state)).await;
+        let body = to_bytes(response.into_body(), usize::MAX).await.unwrap();
+        let body: Value = serde_json::from_slice(&body).unwrap();
+        for key in ["avg_ms", "p50_ms", "p95_ms", "p99_ms"] {
+            assert_eq!(Value::Null, body[key], "'{key}' should be null");
         }
+        assert_eq!(json!({}), body["errors"]);
     }
 }
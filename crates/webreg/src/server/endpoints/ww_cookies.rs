@@ -5,20 +5,22 @@
 use std::sync::Arc;
 
 use axum::extract::{Path, Query, State};
-use axum::http::header::COOKIE;
+use axum::http::header::{CONTENT_TYPE, COOKIE};
 use axum::http::{HeaderMap, StatusCode};
 use axum::response::{IntoResponse, Response};
 use axum::Json;
+use chrono::Utc;
 use serde_json::json;
 use tracing::info;
-use webweg::types::EnrollmentStatus;
+use webweg::types::{EnrollmentStatus, ScheduledSection, WrapperError};
 use webweg::wrapper::input_types::{AddType, ExplicitAddType};
 
 use crate::server::types::{
     ApiErrorType, BodyAddInfo, BodyPlanAdd, BodyScheduleNameChange, BodySectionId,
-    BodySectionScheduleNameId, RawParsedApiResp, RawQueryStr, ScheduleQueryStr,
+    BodySectionScheduleNameId, DateRangeQueryStr, RawParsedApiResp, RawQueryStr, ScheduleQueryStr,
+    ValidationFailure, WaitlistEntry,
 };
-use crate::server::util::{build_add_plan_object, build_add_section_object};
+use crate::server::util::{build_add_plan_object, build_add_section_object, schedule_to_ics};
 use crate::types::WrapperState;
 
 /// A function which should be called when the `register_term` endpoint is called.
@@ -43,6 +45,55 @@ pub async fn post_register_term(
         )
 }
 
+/// A function which should be called when the `whoami` endpoint is called. This gets the
+/// name of the account that the given cookies belong to.
+#[tracing::instrument(level = "info", skip(s))]
+pub async fn get_whoami(
+    headers: HeaderMap,
+    Path(term): Path<String>,
+    State(s): State<Arc<WrapperState>>,
+) -> Response {
+    info!("GET endpoint `whoami` called");
+
+    let cookies = headers.get(COOKIE).unwrap().to_str().unwrap();
+    // The account name isn't specific to a term, so it can't be requested with the shared
+    // cookie wrapper; instead, a wrapper is made just for these cookies.
+    let Some(wrapper) = s
+        .wrapper_settings
+        .wrapper_builder()
+        .with_cookies(cookies)
+        .should_close_after_request(true)
+        .try_build_wrapper()
+    else {
+        return ApiErrorType::from((
+            StatusCode::INTERNAL_SERVER_ERROR,
+            "Unable to create a wrapper for your cookies.",
+            None,
+        ))
+        .into_response();
+    };
+
+    account_name_response(wrapper.get_account_name().await)
+}
+
+/// Converts the result of getting an account's name into a response. WebReg returns an
+/// empty name, rather than an error, when the session isn't valid.
+///
+/// # Parameters
+/// - `result`: The account name, or the error that occurred when getting it.
+///
+/// # Returns
+/// The response.
+fn account_name_response(result: webweg::types::Result<String>) -> Response {
+    match result {
+        Ok(name) if name.trim().is_empty() => {
+            ApiErrorType::from(WrapperError::SessionNotValid).into_response()
+        }
+        Ok(name) => (StatusCode::OK, Json(json!({ "name": name.trim() }))).into_response(),
+        Err(e) => ApiErrorType::from(e).into_response(),
+    }
+}
+
 /// A function which should be called when the `schedule` endpoint is called.
 #[tracing::instrument(level = "info", skip(s))]
 pub async fn get_schedule(
@@ -66,6 +117,54 @@ pub async fn get_schedule(
     .into_response()
 }
 
+/// A function which should be called when the `schedule_ics` endpoint is called.
+#[tracing::instrument(level = "info", skip(s))]
+pub async fn get_schedule_ics(
+    headers: HeaderMap,
+    Query(schedule): Query<ScheduleQueryStr>,
+    Query(range): Query<DateRangeQueryStr>,
+    Path(term): Path<String>,
+    State(s): State<Arc<WrapperState>>,
+) -> Response {
+    info!("GET endpoint `schedule_ics` called");
+
+    let (start, end) = match range.parse() {
+        Ok(o) => o,
+        Err(e) => {
+            return ApiErrorType::from((
+                StatusCode::BAD_REQUEST,
+                "The start and end dates of the term are invalid.",
+                Some(e),
+            ))
+            .into_response()
+        }
+    };
+
+    let cookies = headers.get(COOKIE).unwrap().to_str().unwrap();
+    match s
+        .c_wrapper
+        .req(term.as_str())
+        .override_cookies(cookies)
+        .parsed()
+        .get_schedule(schedule.name.as_deref())
+        .await
+    {
+        Ok(sections) => (
+            StatusCode::OK,
+            [(CONTENT_TYPE, "text/calendar; charset=utf-8")],
+            schedule_to_ics(
+                term.to_uppercase().as_str(),
+                &sections,
+                start,
+                end,
+                Utc::now(),
+            ),
+        )
+            .into_response(),
+        Err(e) => ApiErrorType::from(e).into_response(),
+    }
+}
+
 /// A function which should be called when the `schedule` endpoint is called.
 #[tracing::instrument(level = "info", skip(s))]
 pub async fn get_schedule_list(
@@ -87,6 +186,53 @@ pub async fn get_schedule_list(
     .into_response()
 }
 
+/// A function which should be called when the `waitlist` endpoint is called.
+#[tracing::instrument(level = "info", skip(s))]
+pub async fn get_waitlist(
+    headers: HeaderMap,
+    Query(schedule): Query<ScheduleQueryStr>,
+    Path(term): Path<String>,
+    State(s): State<Arc<WrapperState>>,
+) -> Response {
+    info!("GET endpoint `waitlist` called");
+
+    let cookies = headers.get(COOKIE).unwrap().to_str().unwrap();
+    let req = s
+        .c_wrapper
+        .req(term.as_str())
+        .override_cookies(cookies)
+        .parsed()
+        .get_schedule(schedule.name.as_deref())
+        .await;
+
+    req.map_or_else(
+        |e| ApiErrorType::from(e).into_response(),
+        |sch| (StatusCode::OK, Json(waitlisted_sections(&sch))).into_response(),
+    )
+}
+
+/// Gets the sections in a schedule that the user is waitlisted in.
+///
+/// # Parameters
+/// - `schedule`: The sections in the schedule.
+///
+/// # Returns
+/// The waitlisted sections, along with the user's position on each waitlist.
+fn waitlisted_sections(schedule: &[ScheduledSection]) -> Vec<WaitlistEntry> {
+    schedule
+        .iter()
+        .filter_map(|s| match s.enrolled_status {
+            EnrollmentStatus::Waitlist { waitlist_pos } => Some(WaitlistEntry {
+                subj_course_id: format!("{} {}", s.subject_code, s.course_code),
+                section_id: s.section_id.clone(),
+                section_code: s.section_code.clone(),
+                waitlist_pos,
+            }),
+            _ => None,
+        })
+        .collect()
+}
+
 /// A function which should be called when the `events` endpoint is called.
 #[tracing::instrument(level = "info", skip(s))]
 pub async fn get_events(
@@ -136,6 +282,23 @@ pub async fn post_rename_schedule(
     )
 }
 
+/// Responds to a request whose body is invalid (e.g., because it has an unknown grading
+/// option).
+///
+/// # Parameters
+/// - `reason`: Why the body is invalid.
+///
+/// # Returns
+/// The response.
+fn invalid_body(reason: String) -> Response {
+    ApiErrorType::from((
+        StatusCode::BAD_REQUEST,
+        "The request body is invalid.",
+        Some(reason),
+    ))
+    .into_response()
+}
+
 /// A function which should be called when the `validate_add_section` endpoint is called.
 #[tracing::instrument(level = "info", skip(s))]
 pub async fn post_validate_add_section(
@@ -147,7 +310,10 @@ pub async fn post_validate_add_section(
     info!("POST endpoint `validate_add_section` called");
 
     let cookies = headers.get(COOKIE).unwrap().to_str().unwrap();
-    let add_req = build_add_section_object(&body);
+    let add_req = match build_add_section_object(&body) {
+        Ok(o) => o,
+        Err(e) => return invalid_body(e),
+    };
     let req = s
         .c_wrapper
         .req(term.as_str())
@@ -156,10 +322,31 @@ pub async fn post_validate_add_section(
         .validate_add_section(AddType::DecideForMe, &add_req)
         .await;
 
-    req.map_or_else(
-        |e| ApiErrorType::from(e).into_response(),
-        |b| (StatusCode::OK, Json(json!({ "success": b }))).into_response(),
-    )
+    validation_response(req)
+}
+
+/// Converts the result of validating an add into a response. If WebReg rejects the add,
+/// the response says why (e.g., because the section is full), rather than being an error.
+///
+/// # Parameters
+/// - `result`: Whether the add is valid, or the error that occurred when validating it.
+///
+/// # Returns
+/// The response.
+fn validation_response(result: webweg::types::Result<bool>) -> Response {
+    match result {
+        Ok(b) => (StatusCode::OK, Json(json!({ "success": b }))).into_response(),
+        Err(WrapperError::WebRegError(message)) => (
+            StatusCode::OK,
+            Json(json!({
+                "success": false,
+                "reason": ValidationFailure::from_message(message.as_str()),
+                "message": message,
+            })),
+        )
+            .into_response(),
+        Err(e) => ApiErrorType::from(e).into_response(),
+    }
 }
 
 /// A function which should be called when the `add_section` endpoint is called.
@@ -173,7 +360,10 @@ pub async fn post_add_section(
     info!("POST endpoint `add_section` called");
 
     let cookies = headers.get(COOKIE).unwrap().to_str().unwrap();
-    let add_req = build_add_section_object(&body);
+    let add_req = match build_add_section_object(&body) {
+        Ok(o) => o,
+        Err(e) => return invalid_body(e),
+    };
     let req = s
         .c_wrapper
         .req(term.as_str())
@@ -199,7 +389,10 @@ pub async fn post_validate_add_plan(
     info!("POST endpoint `validate_add_plan` called");
 
     let cookies = headers.get(COOKIE).unwrap().to_str().unwrap();
-    let plan_add = build_add_plan_object(&body);
+    let plan_add = match build_add_plan_object(&body) {
+        Ok(o) => o,
+        Err(e) => return invalid_body(e),
+    };
     let req = s
         .c_wrapper
         .req(term.as_str())
@@ -208,10 +401,7 @@ pub async fn post_validate_add_plan(
         .validate_add_to_plan(&plan_add)
         .await;
 
-    req.map_or_else(
-        |e| ApiErrorType::from(e).into_response(),
-        |b| (StatusCode::OK, Json(json!({ "success": b }))).into_response(),
-    )
+    validation_response(req)
 }
 
 /// A function which should be called when the `add_plan` endpoint is called.
@@ -225,7 +415,10 @@ pub async fn post_add_plan(
     info!("POST endpoint `add_plan` called");
 
     let cookies = headers.get(COOKIE).unwrap().to_str().unwrap();
-    let plan_add = build_add_plan_object(&body);
+    let plan_add = match build_add_plan_object(&body) {
+        Ok(o) => o,
+        Err(e) => return invalid_body(e),
+    };
     let req = s
         .c_wrapper
         .req(term.as_str())
@@ -337,3 +530,166 @@ pub async fn post_drop_section(
         |b| (StatusCode::OK, Json(json!({ "success": b }))).into_response(),
     )
 }
+
+#[cfg(test)]
+mod tests {
+    use axum::body::{to_bytes, Body};
+    use axum::http::Request;
+    use serde_json::Value;
+    use tower::ServiceExt;
+
+    use super::*;
+    use crate::server::create_router;
+
+    async fn to_json(response: Response) -> Value {
+        let body = to_bytes(response.into_body(), usize::MAX).await.unwrap();
+        serde_json::from_slice(&body).unwrap()
+    }
+
+    fn make_scheduled(section_id: &str, enrolled_status: EnrollmentStatus) -> ScheduledSection {
+        ScheduledSection {
+            section_id: section_id.to_owned(),
+            subject_code: "CSE".to_owned(),
+            course_code: "100".to_owned(),
+            course_title: "Advanced Data Structures".to_owned(),
+            section_code: format!("A0{section_id}"),
+            section_capacity: 100,
+            enrolled_count: 100,
+            available_seats: 0,
+            grade_option: "L".to_owned(),
+            all_instructors: vec![],
+            units: 4,
+            enrolled_status,
+            waitlist_ct: 10,
+            meetings: vec![],
+        }
+    }
+
+    #[test]
+    fn test_waitlisted_sections() {
+        assert!(waitlisted_sections(&[]).is_empty());
+
+        let schedule = vec![
+            make_scheduled("1", EnrollmentStatus::Enrolled),
+            make_scheduled("2", EnrollmentStatus::Waitlist { waitlist_pos: 3 }),
+            make_scheduled("3", EnrollmentStatus::Planned),
+            make_scheduled("4", EnrollmentStatus::Unknown),
+            make_scheduled("5", EnrollmentStatus::Waitlist { waitlist_pos: 1 }),
+        ];
+        assert_eq!(
+            vec![
+                WaitlistEntry {
+                    subj_course_id: "CSE 100".to_owned(),
+                    section_id: "2".to_owned(),
+                    section_code: "A02".to_owned(),
+                    waitlist_pos: 3,
+                },
+                WaitlistEntry {
+                    subj_course_id: "CSE 100".to_owned(),
+                    section_id: "5".to_owned(),
+                    section_code: "A05".to_owned(),
+                    waitlist_pos: 1,
+                },
+            ],
+            waitlisted_sections(&schedule)
+        );
+    }
+
+    #[test]
+    fn test_validation_failure_from_message() {
+        for (message, reason) in [
+            ("This section is full.", ValidationFailure::Full),
+            (
+                "The section is restricted to CS26 majors.",
+                ValidationFailure::Restricted,
+            ),
+            (
+                "There is a time conflict with CSE 100 A01.",
+                ValidationFailure::TimeConflict,
+            ),
+            (
+                "You have not met the prerequisite(s) for this course.",
+                ValidationFailure::Prerequisite,
+            ),
+            (
+                "You have exceeded the unit limit for this term.",
+                ValidationFailure::UnitLimit,
+            ),
+            (
+                "You are already enrolled in this course.",
+                ValidationFailure::AlreadyAdded,
+            ),
+            ("Something went wrong.", ValidationFailure::Other),
+        ] {
+            assert_eq!(
+                reason,
+                ValidationFailure::from_message(message),
+                "{message}"
+            );
+        }
+    }
+
+    #[tokio::test]
+    async fn test_validation_response() {
+        let response = validation_response(Ok(true));
+        assert_eq!(StatusCode::OK, response.status());
+        assert_eq!(json!({ "success": true }), to_json(response).await);
+
+        let response = validation_response(Err(WrapperError::WebRegError(
+            "This section is full. You may waitlist instead.".to_owned(),
+        )));
+        assert_eq!(StatusCode::OK, response.status());
+        assert_eq!(
+            json!({
+                "success": false,
+                "reason": "full",
+                "message": "This section is full. You may waitlist instead.",
+            }),
+            to_json(response).await
+        );
+
+        // Errors that aren't WebReg rejecting the add are still errors.
+        let response = validation_response(Err(WrapperError::SessionNotValid));
+        assert_eq!(StatusCode::UNAUTHORIZED, response.status());
+    }
+
+    #[tokio::test]
+    async fn test_account_name_response() {
+        let response = account_name_response(Ok("Doe, John\n".to_owned()));
+        assert_eq!(StatusCode::OK, response.status());
+        assert_eq!(json!({ "name": "Doe, John" }), to_json(response).await);
+
+        for result in [Ok("".to_owned()), Err(WrapperError::SessionNotValid)] {
+            let response = account_name_response(result);
+            assert_eq!(StatusCode::UNAUTHORIZED, response.status());
+            assert!(to_json(response).await["error"]
+                .as_str()
+                .unwrap()
+                .contains("session isn't valid"));
+        }
+    }
+
+    #[tokio::test]
+    async fn test_whoami_is_live_route() {
+        let state = Arc::new(WrapperState::builder().build());
+        let response = create_router(state)
+            .oneshot(
+                Request::builder()
+                    .uri("/live/FA24/whoami")
+                    .body(Body::empty())
+                    .unwrap(),
+            )
+            .await
+            .unwrap();
+
+        // The request should be stopped by the validators (either because there's no API
+        // key or because the wrapper isn't running) rather than falling through to the
+        // fallback.
+        let expected = if cfg!(feature = "auth") {
+            StatusCode::UNAUTHORIZED
+        } else {
+            StatusCode::INTERNAL_SERVER_ERROR
+        };
+        assert_eq!(expected, response.status());
+    }
+}
@@ -1,14 +1,20 @@
 use std::sync::Arc;
 
 use crate::server::types::{
-    ApiErrorType, BodySearchType, CourseQueryStr, RawParsedApiResp, RawQueryStr, SubjListQueryStr,
+    ApiErrorType, BodyCourse, BodySearchType, CourseQueryStr, PrereqQueryStr, RawParsedApiResp,
+    RawQueryStr, SectionEnrollment, SubjListQueryStr,
 };
-use crate::types::WrapperState;
+use crate::server::util::{
+    course_info_batch, export_course_sections_csv, prerequisite_tree, section_by_id,
+};
+use crate::types::{CourseInfoCache, WrapperState};
 use axum::extract::{Path, Query, State};
+use axum::http::header::CONTENT_TYPE;
 use axum::http::StatusCode;
 use axum::response::{IntoResponse, Response};
 use axum::Json;
 use tracing::log::info;
+use webweg::wrapper::input_types::SearchType;
 
 /// A function which should be called when the `terms` endpoint is called.
 #[tracing::instrument(level = "info", skip(s))]
@@ -30,8 +36,130 @@ pub async fn get_course_info(
 ) -> Response {
     info!("GET endpoint `course_info` called");
     let builder = s.wrapper.req(term.as_str());
+    let key = CourseInfoCache::key(term.as_str(), crsc.subject.as_str(), crsc.number.as_str());
     if req_type.raw.unwrap_or(false) {
-        RawParsedApiResp::Raw(
+        let fetch = || async {
+            builder
+                .raw()
+                .get_course_info(&crsc.subject, &crsc.number)
+                .await
+        };
+        RawParsedApiResp::Raw(match s.course_info_cache {
+            Some(ref cache) => cache.raw.get_or_fetch(key, fetch).await,
+            None => fetch().await,
+        })
+    } else {
+        let fetch = || async {
+            builder
+                .parsed()
+                .get_course_info(&crsc.subject, &crsc.number)
+                .await
+        };
+        RawParsedApiResp::Parsed(match s.course_info_cache {
+            Some(ref cache) => cache.parsed.get_or_fetch(key, fetch).await,
+            None => fetch().await,
+        })
+    }
+    .into_response()
+}
+
+/// A function which should be called when the `course_info_csv` endpoint is called.
+#[tracing::instrument(level = "info", skip(s))]
+pub async fn get_course_info_csv(
+    Path(term): Path<String>,
+    Query(crsc): Query<CourseQueryStr>,
+    State(s): State<Arc<WrapperState>>,
+) -> Response {
+    info!("GET endpoint `course_info_csv` called");
+    let key = CourseInfoCache::key(term.as_str(), crsc.subject.as_str(), crsc.number.as_str());
+    let fetch = || async {
+        s.wrapper
+            .req(term.as_str())
+            .parsed()
+            .get_course_info(&crsc.subject, &crsc.number)
+            .await
+    };
+    let sections = match s.course_info_cache {
+        Some(ref cache) => cache.parsed.get_or_fetch(key, fetch).await,
+        None => fetch().await,
+    };
+
+    let sections = match sections {
+        Ok(o) => o,
+        Err(e) => return ApiErrorType::from(e).into_response(),
+    };
+
+    let mut csv = vec![];
+    if let Err(e) = export_course_sections_csv(&sections, &mut csv) {
+        return ApiErrorType::from((
+            StatusCode::INTERNAL_SERVER_ERROR,
+            "The sections could not be written as CSV.",
+            Some(e.to_string()),
+        ))
+        .into_response();
+    }
+
+    (
+        StatusCode::OK,
+        [(CONTENT_TYPE, "text/csv; charset=utf-8")],
+        csv,
+    )
+        .into_response()
+}
+
+/// The maximum number of courses that can be requested from the `course_info_batch`
+/// endpoint at once.
+const MAX_BATCH_COURSES: usize = 50;
+
+/// The maximum number of courses that the `course_info_batch` endpoint looks up at once.
+const BATCH_CONCURRENCY: usize = 4;
+
+/// A function which should be called when the `course_info_batch` endpoint is called.
+#[tracing::instrument(level = "info", skip(s))]
+pub async fn post_course_info_batch(
+    Path(term): Path<String>,
+    State(s): State<Arc<WrapperState>>,
+    Json(courses): Json<Vec<BodyCourse>>,
+) -> Response {
+    info!("POST endpoint `course_info_batch` called");
+    if courses.len() > MAX_BATCH_COURSES {
+        return ApiErrorType::from((
+            StatusCode::BAD_REQUEST,
+            "Too many courses were requested.",
+            Some(format!("max={MAX_BATCH_COURSES}, got={}", courses.len())),
+        ))
+        .into_response();
+    }
+
+    let results = course_info_batch(courses, BATCH_CONCURRENCY, |subject, number| {
+        let s = s.clone();
+        let term = term.clone();
+        async move {
+            s.wrapper
+                .req(term.as_str())
+                .parsed()
+                .get_course_info(subject, number)
+                .await
+        }
+    })
+    .await;
+
+    (StatusCode::OK, Json(results)).into_response()
+}
+
+/// A function which should be called when the `enrollment` endpoint is called.
+#[tracing::instrument(level = "info", skip(s))]
+pub async fn get_enrollment(
+    Path(term): Path<String>,
+    Query(crsc): Query<CourseQueryStr>,
+    Query(req_type): Query<RawQueryStr>,
+    State(s): State<Arc<WrapperState>>,
+) -> Response {
+    info!("GET endpoint `enrollment` called");
+    let builder = s.wrapper.req(term.as_str());
+    if req_type.raw.unwrap_or(false) {
+        // The enrollment counts are parsed from the same response as the course info.
+        RawParsedApiResp::<Vec<SectionEnrollment>>::Raw(
             builder
                 .raw()
                 .get_course_info(crsc.subject, crsc.number)
@@ -41,8 +169,14 @@ pub async fn get_course_info(
         RawParsedApiResp::Parsed(
             builder
                 .parsed()
-                .get_course_info(crsc.subject, crsc.number)
-                .await,
+                .get_enrollment_count(crsc.subject, crsc.number)
+                .await
+                .map(|sections| {
+                    sections
+                        .iter()
+                        .map(SectionEnrollment::from)
+                        .collect::<Vec<_>>()
+                }),
         )
     }
     .into_response()
@@ -54,27 +188,44 @@ pub async fn get_prerequisites(
     Path(term): Path<String>,
     Query(crsc): Query<CourseQueryStr>,
     Query(req_type): Query<RawQueryStr>,
+    Query(prereq_type): Query<PrereqQueryStr>,
     State(s): State<Arc<WrapperState>>,
 ) -> Response {
     info!("GET endpoint `prerequisites` called");
 
+    let as_tree = match prereq_type.parsed.as_deref() {
+        None => false,
+        Some("tree") => true,
+        Some(other) => {
+            return ApiErrorType::from((
+                StatusCode::BAD_REQUEST,
+                "The requested prerequisite format isn't supported.",
+                Some(format!("parsed={other}, supported=tree")),
+            ))
+            .into_response();
+        }
+    };
+
     let builder = s.wrapper.req(term.as_str());
     if req_type.raw.unwrap_or(false) {
-        RawParsedApiResp::Raw(
+        return RawParsedApiResp::<()>::Raw(
             builder
                 .raw()
                 .get_prerequisites(crsc.subject, crsc.number)
                 .await,
         )
+        .into_response();
+    }
+
+    let prereqs = builder
+        .parsed()
+        .get_prerequisites(crsc.subject, crsc.number)
+        .await;
+    if as_tree {
+        RawParsedApiResp::Parsed(prereqs.map(|p| prerequisite_tree(&p))).into_response()
     } else {
-        RawParsedApiResp::Parsed(
-            builder
-                .parsed()
-                .get_prerequisites(crsc.subject, crsc.number)
-                .await,
-        )
+        RawParsedApiResp::Parsed(prereqs).into_response()
     }
-    .into_response()
 }
 
 /// A function which should be called when the `search_courses` endpoint is called.
@@ -178,3 +329,120 @@ pub async fn get_section_text(
         Err(e) => ApiErrorType::from(e).into_response(),
     }
 }
+
+/// A function which should be called when the `section` endpoint is called.
+#[tracing::instrument(level = "info", skip(s))]
+pub async fn get_section(
+    Path((term, section_id)): Path<(String, String)>,
+    State(s): State<Arc<WrapperState>>,
+) -> Response {
+    info!("GET endpoint `section` called");
+    let courses = match s
+        .wrapper
+        .req(term.as_str())
+        .parsed()
+        .search_courses(SearchType::BySection(section_id.clone()))
+        .await
+    {
+        Ok(o) => o,
+        Err(e) => return ApiErrorType::from(e).into_response(),
+    };
+
+    // The search only tells us which course the section belongs to, so we need to get
+    // the course's sections to find the section itself.
+    for course in courses {
+        let section = section_by_id(
+            course.subj_code.as_str(),
+            course.course_code.as_str(),
+            section_id.as_str(),
+            |subject, number| async {
+                s.wrapper
+                    .req(term.as_str())
+                    .parsed()
+                    .get_course_info(subject, number)
+                    .await
+            },
+        )
+        .await;
+
+        match section {
+            Ok(Some(section)) => return (StatusCode::OK, Json(section)).into_response(),
+            Ok(None) => {}
+            Err(e) => return ApiErrorType::from(e).into_response(),
+        }
+    }
+
+    ApiErrorType::from((
+        StatusCode::NOT_FOUND,
+        "The section ID you specified doesn't appear to be offered in the specified term.",
+        Some(section_id),
+    ))
+    .into_response()
+}
+
+#[cfg(test)]
+mod tests {
+    use std::sync::Arc;
+
+    use axum::body::Body;
+    use axum::http::{Request, StatusCode};
+    use tower::ServiceExt;
+    use webweg::types::CourseSection;
+
+    use crate::server::create_router;
+    use crate::server::types::SectionEnrollment;
+    use crate::types::WrapperState;
+
+    #[test]
+    fn test_section_enrollment() {
+        let section = CourseSection {
+            subj_course_id: "CSE 100".to_owned(),
+            section_id: "079912".to_owned(),
+            section_code: "B01".to_owned(),
+            all_instructors: vec!["Doe, John".to_owned()],
+            available_seats: 3,
+            enrolled_ct: 97,
+            total_seats: 100,
+            waitlist_ct: 0,
+            meetings: vec![],
+            is_visible: true,
+        };
+
+        assert_eq!(
+            SectionEnrollment {
+                subj_course_id: "CSE 100".to_owned(),
+                section_id: "079912".to_owned(),
+                section_code: "B01".to_owned(),
+                available_seats: 3,
+                waitlist_ct: 0,
+                total_seats: 100,
+                enrolled_ct: 97,
+            },
+            SectionEnrollment::from(&section)
+        );
+    }
+
+    #[tokio::test]
+    async fn test_enrollment_is_live_route() {
+        let state = Arc::new(WrapperState::builder().build());
+        let response = create_router(state)
+            .oneshot(
+                Request::builder()
+                    .uri("/live/FA24/enrollment?subject=CSE&number=100")
+                    .body(Body::empty())
+                    .unwrap(),
+            )
+            .await
+            .unwrap();
+
+        // The request should be stopped by the validators (either because there's no API
+        // key or because the wrapper isn't running) rather than falling through to the
+        // fallback.
+        let expected = if cfg!(feature = "auth") {
+            StatusCode::UNAUTHORIZED
+        } else {
+            StatusCode::INTERNAL_SERVER_ERROR
+        };
+        assert_eq!(expected, response.status());
+    }
+}
@@ -1,10 +1,10 @@
 use crate::types::WrapperState;
 use axum::extract::{Request, State};
-use axum::http::{header, StatusCode};
+use axum::http::{header, Method, StatusCode};
 use axum::middleware::Next;
 use axum::response::IntoResponse;
 use axum::Json;
-use basicauth::AuthCheckResult;
+use basicauth::{AuthCheckResult, Scope};
 use serde_json::{json, Value};
 use std::sync::Arc;
 use tracing::log::{info, warn};
@@ -45,15 +45,25 @@ pub async fn auth(
         ));
     };
 
-    match state.auth_manager.check_key(prefix, key) {
+    let required = required_scope(req.method(), req.uri().path());
+    match state
+        .auth_manager
+        .check_key_with_scope(prefix, key, required)
+    {
         AuthCheckResult::Valid => {
             info!("The given token has been validated, prefix is '{prefix}'");
             req.extensions_mut().insert(prefix.to_owned());
             Ok(next.run(req).await)
         }
-        AuthCheckResult::NoPrefixOrTokenFound => {
-            info!("The given token is either not valid, or the key doesn't exist.");
+        result @ (AuthCheckResult::NoPrefix | AuthCheckResult::WrongToken) => {
+            if result == AuthCheckResult::NoPrefix {
+                info!("The given prefix does not exist: '{prefix}'");
+            } else {
+                info!("The given token does not match the key, prefix is '{prefix}'");
+            }
 
+            // Clients get the same response either way, so that they can't find out which
+            // prefixes exist.
             Err((
                 StatusCode::UNAUTHORIZED,
                 Json(json!({
@@ -71,5 +81,90 @@ pub async fn auth(
                 })),
             ))
         }
+        AuthCheckResult::InsufficientScope => {
+            info!("The given token is not allowed to make this request, prefix is '{prefix}'");
+
+            Err((
+                StatusCode::FORBIDDEN,
+                Json(json!({
+                    "error": "Token is not allowed to make this request (requires write scope)."
+                })),
+            ))
+        }
+    }
+}
+
+/// The paths of `POST` endpoints that don't change anything, and so only need the read
+/// scope. These only take a body because their input doesn't fit in a query string.
+const READ_ONLY_POST_PATHS: [&str; 1] = ["/course_info_batch"];
+
+/// Gets the scope that a key needs to have to make a request with the given method. Any
+/// request that may change something (e.g., adding a section) requires the write scope.
+///
+/// # Parameters
+/// - `method`: The request method.
+/// - `path`: The request path.
+///
+/// # Returns
+/// The required scope.
+fn required_scope(method: &Method, path: &str) -> Scope {
+    match *method {
+        Method::GET | Method::HEAD | Method::OPTIONS => Scope::Read,
+        Method::POST if READ_ONLY_POST_PATHS.iter().any(|p| path.ends_with(p)) => Scope::Read,
+        _ => Scope::Write,
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::sync::Arc;
+
+    use axum::body::Body;
+    use axum::http::{header, Method, Request, StatusCode};
+    use basicauth::{AuthManager, Scope};
+    use tower::ServiceExt;
+
+    use super::required_scope;
+    use crate::server::create_router;
+    use crate::types::WrapperState;
+
+    #[tokio::test]
+    async fn test_read_key_rejected_on_write_route() {
+        let auth_manager = AuthManager::new(":memory:");
+        let read_key = auth_manager.generate_api_key_with_scope(None::<&str>, Scope::Read);
+        let state = Arc::new(
+            WrapperState::builder()
+                .with_auth_manager(auth_manager)
+                .build(),
+        );
+
+        let response = create_router(state)
+            .oneshot(
+                Request::builder()
+                    .method(Method::POST)
+                    .uri("/live/FA24/add_plan")
+                    .header(header::AUTHORIZATION, format!("Bearer {read_key}"))
+                    .body(Body::empty())
+                    .unwrap(),
+            )
+            .await
+            .unwrap();
+        assert_eq!(StatusCode::FORBIDDEN, response.status());
+    }
+
+    #[test]
+    fn test_required_scope() {
+        assert_eq!(
+            Scope::Read,
+            required_scope(&Method::GET, "/live/FA24/course_info")
+        );
+        assert_eq!(
+            Scope::Read,
+            required_scope(&Method::POST, "/live/FA24/course_info_batch")
+        );
+        assert_eq!(
+            Scope::Write,
+            required_scope(&Method::POST, "/live/FA24/add_plan")
+        );
     }
 }
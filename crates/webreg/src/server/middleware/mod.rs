@@ -1,5 +1,7 @@
 #[cfg(feature = "auth")]
 pub mod auth_validator;
 pub mod cookie_validator;
+#[cfg(feature = "auth")]
+pub mod rate_limiter;
 pub mod running_validator;
 pub mod term_validator;
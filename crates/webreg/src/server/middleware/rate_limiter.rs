@@ -0,0 +1,127 @@
+//! A middleware responsible for rate-limiting requests made with each API key.
+
+use std::sync::Arc;
+use std::time::Instant;
+
+use axum::extract::{Request, State};
+use axum::http::{header, StatusCode};
+use axum::middleware::Next;
+use axum::response::{IntoResponse, Response};
+use axum::Json;
+use serde_json::json;
+use tracing::{info, warn};
+
+use crate::types::WrapperState;
+
+/// A middleware function that rejects requests made with an API key that has made too many
+/// requests recently. This should run after the authentication middleware, which attaches
+/// the key's prefix to the request.
+#[tracing::instrument(skip(state, req, next))]
+pub async fn rate_limit(
+    State(state): State<Arc<WrapperState>>,
+    req: Request,
+    next: Next,
+) -> Response {
+    let (Some(limiter), Some(prefix)) = (
+        state.rate_limiter.as_ref(),
+        req.extensions().get::<String>().cloned(),
+    ) else {
+        return next.run(req).await;
+    };
+
+    info!("Checking the rate limit for prefix '{prefix}'.");
+    match limiter.try_acquire(prefix.as_str(), Instant::now()) {
+        Ok(()) => next.run(req).await,
+        Err(wait) => {
+            let retry_after = wait.as_secs_f64().ceil().max(1.0) as u64;
+            warn!("The prefix '{prefix}' is being rate-limited for {retry_after} second(s).");
+            (
+                StatusCode::TOO_MANY_REQUESTS,
+                [(header::RETRY_AFTER, retry_after.to_string())],
+                Json(json!({
+                    "error": "Too many requests were made with this token. Try again later."
+                })),
+            )
+                .into_response()
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::time::Duration;
+
+    use axum::body::Body;
+    use axum::http::Request;
+    use basicauth::AuthManager;
+    use tower::ServiceExt;
+
+    use crate::server::create_router;
+    use crate::types::{ConfigRateLimit, RateLimiter};
+
+    use super::*;
+
+    #[test]
+    fn test_token_bucket() {
+        let limiter = RateLimiter::new(&ConfigRateLimit {
+            requests_per_minute: 60.0,
+            burst: Some(2),
+        });
+        let start = Instant::now();
+        assert!(limiter.try_acquire("a", start).is_ok());
+        assert!(limiter.try_acquire("a", start).is_ok());
+        assert_eq!(Err(Duration::from_secs(1)), limiter.try_acquire("a", start));
+        // Every key has its own bucket.
+        assert!(limiter.try_acquire("b", start).is_ok());
+
+        // A token is added every second.
+        let later = start + Duration::from_millis(1500);
+        assert!(limiter.try_acquire("a", later).is_ok());
+        assert_eq!(
+            Err(Duration::from_millis(500)),
+            limiter.try_acquire("a", later)
+        );
+    }
+
+    #[tokio::test]
+    async fn test_rate_limit_burst() {
+        let auth_manager = AuthManager::new(":memory:");
+        let key = auth_manager.generate_api_key(None::<&str>);
+        let other_key = auth_manager.generate_api_key(None::<&str>);
+        let state = Arc::new(
+            WrapperState::builder()
+                .with_auth_manager(auth_manager)
+                .with_rate_limit(Some(ConfigRateLimit {
+                    requests_per_minute: 1.0,
+                    burst: Some(3),
+                }))
+                .build(),
+        );
+        let router = create_router(state);
+        let request = |key: &str| {
+            Request::builder()
+                .uri("/health")
+                .header(header::AUTHORIZATION, format!("Bearer {key}"))
+                .body(Body::empty())
+                .unwrap()
+        };
+
+        for _ in 0..3 {
+            let response = router.clone().oneshot(request(&key)).await.unwrap();
+            assert_eq!(StatusCode::OK, response.status());
+        }
+
+        let response = router.clone().oneshot(request(&key)).await.unwrap();
+        assert_eq!(StatusCode::TOO_MANY_REQUESTS, response.status());
+        let retry_after = response.headers()[header::RETRY_AFTER]
+            .to_str()
+            .unwrap()
+            .parse::<u64>()
+            .unwrap();
+        assert!((1..=60).contains(&retry_after));
+
+        // Other keys aren't affected.
+        let response = router.oneshot(request(&other_key)).await.unwrap();
+        assert_eq!(StatusCode::OK, response.status());
+    }
+}
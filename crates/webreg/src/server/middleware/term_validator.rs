@@ -1,5 +1,6 @@
 //! A middleware responsible for ensuring the term is valid.
 
+use std::collections::HashMap;
 use std::sync::Arc;
 
 use axum::extract::{Path, Request, State};
@@ -10,27 +11,84 @@ use axum::Json;
 use serde_json::{json, Value};
 use tracing::info;
 
-use crate::types::WrapperState;
+use crate::types::{WrapperMap, WrapperState};
 
 /// A middleware function that validates a term that's passed as part of the path
-/// is supported by the server.
+/// is supported by the server. The path may have other parameters (e.g., a section ID)
+/// besides the term, so the parameters are extracted by name.
 #[tracing::instrument(skip(state, req, next))]
 pub async fn validate_term(
-    Path(term): Path<String>,
+    Path(params): Path<HashMap<String, String>>,
     State(state): State<Arc<WrapperState>>,
     req: Request,
     next: Next,
 ) -> Result<impl IntoResponse, (StatusCode, Json<Value>)> {
     info!("Validating if term is supported.");
-    let term = term.to_uppercase();
-    if state.all_terms.contains_key(&term) {
-        Ok(next.run(req).await)
-    } else {
-        Err((
-            StatusCode::NOT_FOUND,
-            Json(json!({
-                "error": "The specified term cannot be found"
-            })),
-        ))
+    let term = params.get("term").map(String::as_str).unwrap_or_default();
+    check_term(&state.all_terms.read().unwrap(), term)?;
+    Ok(next.run(req).await)
+}
+
+/// Checks that the given term is one of the terms that the server was configured with.
+///
+/// # Parameters
+/// - `all_terms`: All terms that the server was configured with.
+/// - `term`: The term to check.
+///
+/// # Returns
+/// Nothing if the term is supported, or the error response (which includes all supported
+/// terms) otherwise.
+fn check_term(all_terms: &WrapperMap, term: &str) -> Result<(), (StatusCode, Json<Value>)> {
+    if all_terms.contains_key(&term.to_uppercase()) {
+        return Ok(());
+    }
+
+    let mut valid_terms = all_terms.keys().collect::<Vec<_>>();
+    valid_terms.sort();
+    Err((
+        StatusCode::NOT_FOUND,
+        Json(json!({
+            "error": "The specified term cannot be found",
+            "valid_terms": valid_terms
+        })),
+    ))
+}
+
+#[cfg(test)]
+mod tests {
+    use std::sync::Arc;
+
+    use axum::http::StatusCode;
+    use serde_json::json;
+
+    use super::check_term;
+    use crate::types::{TermInfo, WrapperMap};
+
+    fn make_terms(terms: &[&str]) -> WrapperMap {
+        terms
+            .iter()
+            .map(|term| (term.to_string(), Arc::new(TermInfo::for_test(term))))
+            .collect()
+    }
+
+    #[test]
+    fn test_valid_term() {
+        let terms = make_terms(&["FA24", "WI25"]);
+        assert!(check_term(&terms, "FA24").is_ok());
+        assert!(check_term(&terms, "wi25").is_ok());
+    }
+
+    #[test]
+    fn test_invalid_term() {
+        let terms = make_terms(&["WI25", "FA24"]);
+        let (status, body) = check_term(&terms, "SP23").unwrap_err();
+        assert_eq!(StatusCode::NOT_FOUND, status);
+        assert_eq!(
+            json!({
+                "error": "The specified term cannot be found",
+                "valid_terms": ["FA24", "WI25"]
+            }),
+            body.0
+        );
     }
 }
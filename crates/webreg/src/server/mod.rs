@@ -1,9 +1,15 @@
 use std::sync::Arc;
 
+use axum::http::{header, HeaderValue, Method};
 use axum::routing::{get, post};
 use axum::{middleware as mw, Router};
+use tower_http::compression::CompressionLayer;
+use tower_http::cors::{AllowOrigin, CorsLayer};
+use tracing::warn;
 
-use crate::server::endpoints::{status, ww_cookies, ww_general};
+#[cfg(feature = "auth")]
+use crate::server::endpoints::session;
+use crate::server::endpoints::{control, history, status, ww_cookies, ww_general};
 use crate::server::middleware::*;
 use crate::types::WrapperState;
 
@@ -34,8 +40,11 @@ pub fn create_router(app_state: Arc<WrapperState>) -> Router {
             post(ww_cookies::post_validate_add_plan),
         )
         .route("/remove_plan", post(ww_cookies::post_remove_plan))
+        .route("/whoami", get(ww_cookies::get_whoami))
         .route("/schedule", get(ww_cookies::get_schedule))
+        .route("/schedule_ics", get(ww_cookies::get_schedule_ics))
         .route("/schedule_list", get(ww_cookies::get_schedule_list))
+        .route("/waitlist", get(ww_cookies::get_waitlist))
         .route("/register_term", post(ww_cookies::post_register_term))
         .route("/events", get(ww_cookies::get_events))
         .route("/rename_schedule", post(ww_cookies::post_rename_schedule))
@@ -44,12 +53,19 @@ pub fn create_router(app_state: Arc<WrapperState>) -> Router {
     // General router
     let parsed_router = Router::new()
         .route("/course_info", get(ww_general::get_course_info))
+        .route("/course_info_csv", get(ww_general::get_course_info_csv))
+        .route(
+            "/course_info_batch",
+            post(ww_general::post_course_info_batch),
+        )
+        .route("/enrollment", get(ww_general::get_enrollment))
         .route("/prerequisites", get(ww_general::get_prerequisites))
         .route("/search", get(ww_general::get_search_courses))
         .route("/department_codes", get(ww_general::get_department_codes))
         .route("/subject_codes", get(ww_general::get_subject_codes))
         .route("/course_text", get(ww_general::get_course_text))
         .route("/section_text", get(ww_general::get_section_text))
+        .route("/section/:id", get(ww_general::get_section))
         .merge(cookie_router)
         .layer(mw::from_fn_with_state(
             app_state.clone(),
@@ -64,23 +80,240 @@ pub fn create_router(app_state: Arc<WrapperState>) -> Router {
             running_validator::validate_wrapper_running,
         ));
 
+    // Router for controlling the tracker of a single term.
+    let control_router = Router::new()
+        .route("/pause", post(control::post_pause_term))
+        .route("/resume", post(control::post_resume_term))
+        .layer(mw::from_fn_with_state(
+            app_state.clone(),
+            term_validator::validate_term,
+        ));
+
     let router = Router::new()
         .route("/health", get(status::get_health))
+        .route("/openapi.json", get(status::get_openapi))
         .nest("/live/:term", webreg_router)
+        .nest("/control/:term", control_router)
         .route("/terms", get(ww_general::get_all_terms))
         .route("/timing/:term", get(status::get_timing_stats))
+        .route("/history/:term", get(history::get_history))
+        .route("/metrics", get(status::get_metrics))
         .route("/login_stat/:stat", get(status::get_login_script_stats))
+        .fallback(status::get_fallback)
         .with_state(app_state.clone());
 
+    // This endpoint lets the caller replace the session cookies used by the scraper, so
+    // it should only be available if requests are authenticated.
     #[cfg(feature = "auth")]
-    {
-        router.layer(mw::from_fn_with_state(
+    let router = router
+        .route("/session/cookies", post(session::post_session_cookies))
+        .with_state(app_state.clone())
+        .layer(mw::from_fn_with_state(
             app_state.clone(),
-            auth_validator::auth,
+            rate_limiter::rate_limit,
         ))
+        .layer(mw::from_fn_with_state(
+            app_state.clone(),
+            auth_validator::auth,
+        ));
+
+    // Responses are compressed if the client accepts it, since course data can be large.
+    let router = router.layer(CompressionLayer::new());
+
+    // The CORS layer goes on the outside so that preflight requests, which don't include
+    // any credentials, are answered before reaching the authentication middleware.
+    match cors_layer(&app_state.allowed_origins) {
+        Some(cors) => router.layer(cors),
+        None => router,
+    }
+}
+
+/// Creates the layer that adds CORS headers to responses.
+///
+/// # Parameters
+/// - `origins`: The origins that cross-origin requests are allowed from, or `*` for any
+///   origin. Origins that aren't valid header values are ignored.
+///
+/// # Returns
+/// The layer, or `None` if no origins are allowed.
+fn cors_layer(origins: &[String]) -> Option<CorsLayer> {
+    let allow_origin = if origins.iter().any(|origin| origin == "*") {
+        AllowOrigin::any()
+    } else {
+        let origins = origins
+            .iter()
+            .filter_map(|origin| match HeaderValue::from_str(origin) {
+                Ok(value) => Some(value),
+                Err(_) => {
+                    warn!("Ignoring the allowed origin '{origin}', since it isn't valid.");
+                    None
+                }
+            })
+            .collect::<Vec<_>>();
+        if origins.is_empty() {
+            return None;
+        }
+
+        AllowOrigin::list(origins)
+    };
+
+    Some(
+        CorsLayer::new()
+            .allow_origin(allow_origin)
+            .allow_methods([Method::GET, Method::POST])
+            .allow_headers([header::AUTHORIZATION, header::CONTENT_TYPE]),
+    )
+}
+
+#[cfg(test)]
+mod tests {
+    use std::io::Read;
+
+    use axum::body::{to_bytes, Body};
+    use axum::http::{Request, StatusCode};
+    use flate2::read::GzDecoder;
+    use serde_json::{json, Value};
+    use tower::ServiceExt;
+
+    use super::*;
+
+    async fn preflight(origins: Vec<String>, origin: &str) -> axum::response::Response {
+        let state = Arc::new(
+            WrapperState::builder()
+                .with_allowed_origins(origins)
+                .build(),
+        );
+        create_router(state)
+            .oneshot(
+                Request::builder()
+                    .method(Method::OPTIONS)
+                    .uri("/health")
+                    .header(header::ORIGIN, origin)
+                    .header(header::ACCESS_CONTROL_REQUEST_METHOD, "GET")
+                    .body(Body::empty())
+                    .unwrap(),
+            )
+            .await
+            .unwrap()
+    }
+
+    #[tokio::test]
+    async fn test_cors_preflight() {
+        let origins = vec!["https://dashboard.example.com".to_owned()];
+        let response = preflight(origins.clone(), "https://dashboard.example.com").await;
+        assert_eq!(StatusCode::OK, response.status());
+        assert_eq!(
+            "https://dashboard.example.com",
+            response.headers()[header::ACCESS_CONTROL_ALLOW_ORIGIN]
+        );
+        assert!(response
+            .headers()
+            .contains_key(header::ACCESS_CONTROL_ALLOW_METHODS));
+
+        // Origins that aren't allowed don't get the header.
+        let response = preflight(origins, "https://elsewhere.example.com").await;
+        assert!(!response
+            .headers()
+            .contains_key(header::ACCESS_CONTROL_ALLOW_ORIGIN));
     }
-    #[cfg(not(feature = "auth"))]
-    {
-        router
+
+    #[tokio::test]
+    async fn test_compression() {
+        let state = Arc::new(WrapperState::builder().build());
+        let response = create_router(state)
+            .oneshot(
+                Request::builder()
+                    .uri("/health")
+                    .header(header::ACCEPT_ENCODING, "gzip")
+                    .body(Body::empty())
+                    .unwrap(),
+            )
+            .await
+            .unwrap();
+        assert_eq!("gzip", response.headers()[header::CONTENT_ENCODING]);
+
+        let body = to_bytes(response.into_body(), usize::MAX).await.unwrap();
+        let mut decompressed = String::new();
+        GzDecoder::new(body.as_ref())
+            .read_to_string(&mut decompressed)
+            .unwrap();
+        let body: Value = serde_json::from_str(&decompressed).unwrap();
+        // Without a token, the authentication middleware responds instead, but that
+        // response should be compressed too.
+        let expected = if cfg!(feature = "auth") {
+            json!({ "error": "You didn't provide a bearer token." })
+        } else {
+            json!({ "api": false, "paused": [], "schema_version": crate::types::SCHEMA_VERSION })
+        };
+        assert_eq!(expected, body);
+    }
+
+    #[tokio::test]
+    async fn test_route_with_term_and_id() {
+        // The wrapper's requests go through a proxy that refuses connections, so they fail
+        // without reaching WebReg.
+        let proxy = {
+            let listener = tokio::net::TcpListener::bind("127.0.0.1:0").await.unwrap();
+            format!("http://{}", listener.local_addr().unwrap())
+        };
+        let client = webweg_reqwest::Client::builder()
+            .proxy(webweg_reqwest::Proxy::all(proxy).unwrap())
+            .build()
+            .unwrap();
+        let wrapper = webweg::wrapper::WebRegWrapper::builder()
+            .with_client(client)
+            .with_cookies("abc")
+            .try_build_wrapper()
+            .unwrap();
+
+        let builder = WrapperState::builder()
+            .with_terms(vec![crate::types::TermInfo::for_test("FA24")])
+            .with_wrapper(wrapper);
+        #[cfg(feature = "auth")]
+        let (builder, key) = {
+            let auth_manager = basicauth::AuthManager::new(":memory:");
+            let key = auth_manager.generate_api_key(None::<&str>);
+            (builder.with_auth_manager(auth_manager), key)
+        };
+        let state = Arc::new(builder.build());
+        state
+            .is_running
+            .store(true, std::sync::atomic::Ordering::SeqCst);
+
+        let request = |uri: &str| {
+            let request = Request::builder().uri(uri);
+            #[cfg(feature = "auth")]
+            let request = request.header(header::AUTHORIZATION, format!("Bearer {key}"));
+            request.body(Body::empty()).unwrap()
+        };
+
+        // The handler should be reached, and fail because WebReg couldn't be reached.
+        let response = create_router(state.clone())
+            .oneshot(request("/live/FA24/section/079911"))
+            .await
+            .unwrap();
+        assert_eq!(StatusCode::INTERNAL_SERVER_ERROR, response.status());
+        let body = to_bytes(response.into_body(), usize::MAX).await.unwrap();
+        let body: Value = serde_json::from_slice(&body).unwrap();
+        assert_eq!("An internal request error occurred.", body["error"]);
+
+        // The term should still be validated.
+        let response = create_router(state)
+            .oneshot(request("/live/SP30/section/079911"))
+            .await
+            .unwrap();
+        assert_eq!(StatusCode::NOT_FOUND, response.status());
+        let body = to_bytes(response.into_body(), usize::MAX).await.unwrap();
+        let body: Value = serde_json::from_slice(&body).unwrap();
+        assert_eq!(json!(["FA24"]), body["valid_terms"]);
+    }
+
+    #[tokio::test]
+    async fn test_no_cors_by_default() {
+        let response = preflight(vec![], "https://dashboard.example.com").await;
+        assert!(!response
+            .headers()
+            .contains_key(header::ACCESS_CONTROL_ALLOW_ORIGIN));
+        assert!(cors_layer(&["not\nvalid".to_owned()]).is_none());
     }
 }
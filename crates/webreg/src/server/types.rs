@@ -1,12 +1,13 @@
 use std::borrow::Cow;
-use std::fmt::Debug;
+use std::fmt::{Debug, Display, Formatter};
 
 use axum::http::StatusCode;
 use axum::response::{IntoResponse, Response};
 use axum::Json;
+use chrono::NaiveDate;
 use serde::{Deserialize, Serialize};
 use serde_json::{json, Value};
-use webweg::types::{SectionIdNotFoundContext, WrapperError};
+use webweg::types::{CourseSection, MeetingDay, SectionIdNotFoundContext, WrapperError};
 use webweg::wrapper::input_types::{
     CourseLevelFilter, DayOfWeek, SearchRequestBuilder, SearchType,
 };
@@ -35,6 +36,12 @@ pub struct BodyScheduleNameChange {
     pub new_name: String,
 }
 
+#[cfg(feature = "auth")]
+#[derive(Deserialize)]
+pub struct BodyCookies {
+    pub cookie: String,
+}
+
 #[derive(Deserialize, Debug)]
 pub struct BodyAddInfo {
     #[serde(rename = "sectionId")]
@@ -73,6 +80,35 @@ pub struct ScheduleQueryStr {
     pub name: Option<String>,
 }
 
+/// A structure meant for a query string, intended to have the user provide the first and
+/// last day (in the form `YYYY-MM-DD`) that a term's classes meet.
+#[derive(Deserialize, Debug)]
+pub struct DateRangeQueryStr {
+    pub start: String,
+    pub end: String,
+}
+
+impl DateRangeQueryStr {
+    /// Parses the first and last day.
+    ///
+    /// # Returns
+    /// The first and last day, or the reason why they're invalid.
+    pub fn parse(&self) -> Result<(NaiveDate, NaiveDate), String> {
+        let parse_date = |date: &str| {
+            NaiveDate::parse_from_str(date.trim(), "%Y-%m-%d")
+                .map_err(|_| format!("'{date}' is not a date in the form YYYY-MM-DD"))
+        };
+
+        let start = parse_date(self.start.as_str())?;
+        let end = parse_date(self.end.as_str())?;
+        if start > end {
+            return Err(format!("the start ({start}) is after the end ({end})"));
+        }
+
+        Ok((start, end))
+    }
+}
+
 /// A structure meant for a query string, intended to have the user provide a course to
 /// search up in some way.
 #[derive(Deserialize, Debug)]
@@ -81,6 +117,24 @@ pub struct CourseQueryStr {
     pub number: String,
 }
 
+/// A structure representing one course in a request body for the `course_info_batch`
+/// endpoint.
+#[derive(Deserialize, Debug)]
+pub struct BodyCourse {
+    pub subject: String,
+    pub number: String,
+}
+
+/// The result of looking up a single course for the `course_info_batch` endpoint.
+#[derive(Serialize, Debug)]
+#[serde(untagged)]
+pub enum BatchCourseResult {
+    /// The course's sections.
+    Sections(Vec<CourseSection>),
+    /// The course couldn't be looked up.
+    Error { error: String },
+}
+
 /// A structure meant for a query string, intended to have the user provide a "list" of
 /// subject code (e.g., CSE)
 #[derive(Deserialize, Debug)]
@@ -88,6 +142,13 @@ pub struct SubjListQueryStr {
     pub subjects: String,
 }
 
+/// A structure meant for a query string, intended to give users the ability to choose
+/// how prerequisites are represented.
+#[derive(Deserialize, Debug)]
+pub struct PrereqQueryStr {
+    pub parsed: Option<String>,
+}
+
 /// A structure meant for a query string, intended to give users the ability to control
 /// the type of response they wanted.
 #[derive(Deserialize, Debug)]
@@ -95,6 +156,225 @@ pub struct RawQueryStr {
     pub raw: Option<bool>,
 }
 
+/// A structure representing a section that the user is currently waitlisted in.
+#[derive(Serialize, Debug, PartialEq, Eq)]
+pub struct WaitlistEntry {
+    /// The subject, course ID. For example, `CSE 100`.
+    pub subj_course_id: String,
+    /// The section ID. For example, `079912`.
+    pub section_id: String,
+    /// The section code. For example, `B01`.
+    pub section_code: String,
+    /// The user's current position on the waitlist.
+    pub waitlist_pos: i64,
+}
+
+/// A structure representing when the login script first logged into WebReg.
+#[derive(Serialize, Debug, PartialEq, Eq)]
+pub struct LoginStart {
+    /// The time, in milliseconds since the Unix epoch. This is `0` if the login script
+    /// hasn't logged in yet.
+    pub epoch: i64,
+}
+
+/// A structure representing a single login made by the login script after it first
+/// logged in.
+#[derive(Serialize, Debug, PartialEq, Eq)]
+pub struct LoginHistoryEntry {
+    /// The time, in milliseconds since the Unix epoch.
+    pub timestamp: i64,
+    /// Whether the login was successful. The login script only records successful
+    /// logins, so this is currently always `true`.
+    pub success: bool,
+}
+
+/// An enum representing the statistics that the login script can report.
+#[derive(Serialize, Debug, PartialEq, Eq)]
+#[serde(untagged)]
+pub enum LoginStat {
+    Start(LoginStart),
+    History(Vec<LoginHistoryEntry>),
+}
+
+impl LoginStat {
+    /// Parses the login script's response for the given statistic.
+    ///
+    /// # Parameters
+    /// - `stat_type`: The statistic, either `start` or `history`.
+    /// - `text`: The login script's response.
+    ///
+    /// # Returns
+    /// The statistic, or an error if the response isn't in the expected shape.
+    pub fn parse(stat_type: &str, text: &str) -> Result<Self, String> {
+        match stat_type {
+            "start" => serde_json::from_str::<i64>(text)
+                .map(|epoch| LoginStat::Start(LoginStart { epoch }))
+                .map_err(|e| e.to_string()),
+            "history" => serde_json::from_str::<Vec<i64>>(text)
+                .map(|timestamps| {
+                    LoginStat::History(
+                        timestamps
+                            .into_iter()
+                            .map(|timestamp| LoginHistoryEntry {
+                                timestamp,
+                                success: true,
+                            })
+                            .collect(),
+                    )
+                })
+                .map_err(|e| e.to_string()),
+            _ => Err(format!("unknown statistic '{stat_type}'")),
+        }
+    }
+}
+
+/// A structure representing the enrollment numbers of a section.
+#[derive(Serialize, Debug, PartialEq, Eq)]
+pub struct SectionEnrollment {
+    /// The subject, course ID. For example, `CSE 100`.
+    pub subj_course_id: String,
+    /// The section ID. For example, `079912`.
+    pub section_id: String,
+    /// The section code. For example, `B01`.
+    pub section_code: String,
+    /// The number of available seats.
+    pub available_seats: i64,
+    /// The number of students on the waitlist.
+    pub waitlist_ct: i64,
+    /// The total number of seats.
+    pub total_seats: i64,
+    /// The number of students enrolled.
+    pub enrolled_ct: i64,
+}
+
+impl From<&CourseSection> for SectionEnrollment {
+    fn from(section: &CourseSection) -> Self {
+        Self {
+            subj_course_id: section.subj_course_id.clone(),
+            section_id: section.section_id.clone(),
+            section_code: section.section_code.clone(),
+            available_seats: section.available_seats,
+            waitlist_ct: section.waitlist_ct,
+            total_seats: section.total_seats,
+            enrolled_ct: section.enrolled_ct,
+        }
+    }
+}
+
+/// A structure representing a single booking of a room by a section meeting.
+#[derive(Serialize, Debug, Clone)]
+pub struct TimeSlot {
+    /// The subject, course ID. For example, `CSE 100`.
+    pub subj_course_id: String,
+    /// The meeting type. For example, `LE` or `DI`.
+    pub meeting_type: String,
+    /// The day(s) that this meeting occurs on.
+    pub meeting_days: MeetingDay,
+    /// The start hour.
+    pub start_hr: u32,
+    /// The start minute.
+    pub start_min: u32,
+    /// The end hour.
+    pub end_hr: u32,
+    /// The end minute.
+    pub end_min: u32,
+    /// Whether this booking overlaps with another booking of the same room. This
+    /// shouldn't happen, and usually indicates an issue with the data.
+    pub conflict: bool,
+}
+
+/// A requirement that must be satisfied to enroll in a course.
+#[derive(Serialize, Debug, Clone, PartialEq, Eq)]
+#[serde(rename_all = "camelCase")]
+pub enum Prereq {
+    /// A course that must have been taken. For example, `CSE 12`.
+    Course(String),
+    /// An exam that must have been passed.
+    Exam(String),
+    /// At least one of these requirements must be satisfied.
+    AnyOf(Vec<Prereq>),
+    /// All of these requirements must be satisfied.
+    AllOf(Vec<Prereq>),
+}
+
+impl Display for Prereq {
+    fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
+        let (reqs, sep) = match self {
+            Prereq::Course(c) | Prereq::Exam(c) => return write!(f, "{c}"),
+            Prereq::AnyOf(reqs) => (reqs, " or "),
+            Prereq::AllOf(reqs) => (reqs, " and "),
+        };
+
+        for (i, req) in reqs.iter().enumerate() {
+            if i > 0 {
+                write!(f, "{sep}")?;
+            }
+
+            match req {
+                Prereq::AnyOf(_) | Prereq::AllOf(_) => write!(f, "({req})")?,
+                _ => write!(f, "{req}")?,
+            }
+        }
+
+        Ok(())
+    }
+}
+
+/// Why WebReg won't let a section be added to a user's schedule or plan, as decided from
+/// the message that WebReg gives.
+#[derive(Serialize, Debug, Clone, Copy, PartialEq, Eq)]
+#[serde(rename_all = "snake_case")]
+pub enum ValidationFailure {
+    /// The section meets at the same time as another section in the schedule.
+    TimeConflict,
+    /// The section has no seats left.
+    Full,
+    /// The section is restricted to certain majors, colleges, or class levels.
+    Restricted,
+    /// The user doesn't meet the section's prerequisites.
+    Prerequisite,
+    /// Adding the section would exceed the user's unit limit.
+    UnitLimit,
+    /// The user is already enrolled in, waitlisted for, or planning the course.
+    AlreadyAdded,
+    /// Any other reason.
+    Other,
+}
+
+impl ValidationFailure {
+    /// The words that WebReg uses to describe each reason, in the order that they're
+    /// checked; a section can, for example, be reported as both full and conflicting.
+    const KEYWORDS: [(ValidationFailure, &'static [&'static str]); 6] = [
+        (ValidationFailure::TimeConflict, &["conflict"]),
+        (
+            ValidationFailure::Full,
+            &["full", "no seats", "no available seats"],
+        ),
+        (ValidationFailure::Restricted, &["restrict", "major"]),
+        (ValidationFailure::Prerequisite, &["prerequisite"]),
+        (
+            ValidationFailure::UnitLimit,
+            &["unit limit", "maximum units", "units allowed"],
+        ),
+        (ValidationFailure::AlreadyAdded, &["already"]),
+    ];
+
+    /// Decides why a section couldn't be added from the message that WebReg gave.
+    ///
+    /// # Parameters
+    /// - `message`: The message.
+    ///
+    /// # Returns
+    /// The reason.
+    pub fn from_message(message: &str) -> Self {
+        let message = message.to_lowercase();
+        Self::KEYWORDS
+            .iter()
+            .find(|(_, keywords)| keywords.iter().any(|k| message.contains(k)))
+            .map_or(ValidationFailure::Other, |(reason, _)| *reason)
+    }
+}
+
 /// An enum that represents some sort of an error by the API.
 pub enum ApiErrorType<'a> {
     /// Whether the error was from WebReg.
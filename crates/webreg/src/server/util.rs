@@ -1,6 +1,52 @@
-use crate::server::types::{BodyAddInfo, BodyPlanAdd};
+use std::borrow::Cow;
+use std::collections::HashMap;
+use std::future::Future;
+use std::io::{self, Write};
+
+use crate::server::types::{
+    BatchCourseResult, BodyAddInfo, BodyCourse, BodyPlanAdd, Prereq, TimeSlot,
+};
+use chrono::{DateTime, Datelike, NaiveDate, Utc, Weekday};
+use futures::stream::{self, StreamExt};
+use webweg::types::{
+    CourseSection, MeetingDay, PrerequisiteInfo, Result as WrapperResult, ScheduledSection,
+};
 use webweg::wrapper::input_types::{EnrollWaitAdd, GradeOption, PlanAdd};
 
+/// The days of the week, as WebReg and iCalendar name them.
+const WEEKDAYS: [(&str, &str, Weekday); 7] = [
+    ("M", "MO", Weekday::Mon),
+    ("Tu", "TU", Weekday::Tue),
+    ("W", "WE", Weekday::Wed),
+    ("Th", "TH", Weekday::Thu),
+    ("F", "FR", Weekday::Fri),
+    ("Sa", "SA", Weekday::Sat),
+    ("Su", "SU", Weekday::Sun),
+];
+
+/// Parses the grading option from a request body.
+///
+/// # Parameters
+/// - `grading_option`: The grading option, which should be one of `L`, `P`, or `S`
+///   (case-insensitive). If this isn't specified, letter grading is used.
+///
+/// # Returns
+/// The grading option, or the reason why it's invalid.
+pub fn parse_grade_option(grading_option: Option<&str>) -> Result<GradeOption, String> {
+    let Some(g) = grading_option else {
+        return Ok(GradeOption::L);
+    };
+
+    match g.trim().to_ascii_uppercase().as_str() {
+        "L" => Ok(GradeOption::L),
+        "P" => Ok(GradeOption::P),
+        "S" => Ok(GradeOption::S),
+        _ => Err(format!(
+            "'{g}' is not a valid grading option; expected one of L, P, or S"
+        )),
+    }
+}
+
 /// A helper function to automatically convert the given grading option and unit count from
 /// a request body to something that the library can use.
 ///
@@ -9,24 +55,16 @@ use webweg::wrapper::input_types::{EnrollWaitAdd, GradeOption, PlanAdd};
 /// - `unit_count`: The unit count.
 ///
 /// # Returns
-/// The "parsed" version that can be used by the library.
+/// The "parsed" version that can be used by the library, or the reason why the grading
+/// option is invalid.
 pub fn parse_grade_option_unit_count(
     grading_option: &Option<String>,
     unit_count: Option<i64>,
-) -> (GradeOption, Option<u8>) {
-    let grading_option = match grading_option {
-        Some(g) => match g.as_str() {
-            "L" | "l" => GradeOption::L,
-            "P" | "p" => GradeOption::P,
-            "S" | "s" => GradeOption::S,
-            _ => GradeOption::L,
-        },
-        None => GradeOption::L,
-    };
-
+) -> Result<(GradeOption, Option<u8>), String> {
+    let grading_option = parse_grade_option(grading_option.as_deref())?;
     let unit_count = unit_count.and_then(|d| u8::try_from(d).ok());
 
-    (grading_option, unit_count)
+    Ok((grading_option, unit_count))
 }
 
 /// Builds the `PlanAdd` object that can be used for the library.
@@ -35,10 +73,10 @@ pub fn parse_grade_option_unit_count(
 /// - `body`: The body from the request.
 ///
 /// # Returns
-/// The `PlanAdd` object.
-pub fn build_add_plan_object(body: &BodyPlanAdd) -> PlanAdd {
+/// The `PlanAdd` object, or the reason why the body is invalid.
+pub fn build_add_plan_object(body: &BodyPlanAdd) -> Result<PlanAdd<'_>, String> {
     let (grading_option, unit_count) =
-        parse_grade_option_unit_count(&body.grading_option, Some(body.unit_count));
+        parse_grade_option_unit_count(&body.grading_option, Some(body.unit_count))?;
 
     let mut plan_add = PlanAdd::builder()
         .with_subject_code(body.subject_code.as_str())
@@ -52,7 +90,7 @@ pub fn build_add_plan_object(body: &BodyPlanAdd) -> PlanAdd {
         plan_add = plan_add.with_schedule_name(s);
     }
 
-    plan_add.try_build().unwrap()
+    Ok(plan_add.try_build().unwrap())
 }
 
 /// Builds the `EnrollWaitAdd` object that can be used for the library.
@@ -61,10 +99,10 @@ pub fn build_add_plan_object(body: &BodyPlanAdd) -> PlanAdd {
 /// - `body`: The body from the request.
 ///
 /// # Returns
-/// The `EnrollWaitAdd` object.
-pub fn build_add_section_object(body: &BodyAddInfo) -> EnrollWaitAdd {
+/// The `EnrollWaitAdd` object, or the reason why the body is invalid.
+pub fn build_add_section_object(body: &BodyAddInfo) -> Result<EnrollWaitAdd<'_>, String> {
     let (grading_option, unit_count) =
-        parse_grade_option_unit_count(&body.grading_option, body.unit_count);
+        parse_grade_option_unit_count(&body.grading_option, body.unit_count)?;
 
     let mut add_req = EnrollWaitAdd::builder()
         .with_section_id(body.section_id.as_str())
@@ -74,5 +112,771 @@ pub fn build_add_section_object(body: &BodyAddInfo) -> EnrollWaitAdd {
         add_req = add_req.with_unit_count(u);
     }
 
-    add_req.try_build().unwrap()
+    Ok(add_req.try_build().unwrap())
+}
+
+/// Aggregates all meetings from the given sections by the room that they're held in.
+///
+/// Since each section includes the meetings that it shares with other sections (e.g.,
+/// every discussion section includes its lecture), identical meetings for the same course
+/// are only counted once. Any bookings that overlap with another booking of the same room
+/// are flagged as a conflict. Note that a recurring meeting is never considered to be in
+/// conflict with a one-time meeting, since one-time meetings (like finals) generally take
+/// place when recurring meetings don't.
+///
+/// # Parameters
+/// - `sections`: The sections.
+///
+/// # Returns
+/// A map from the `(building, room)` to all bookings of that room, ordered by start time.
+#[allow(dead_code)]
+pub fn room_usage(sections: &[CourseSection]) -> HashMap<(String, String), Vec<TimeSlot>> {
+    let mut usage: HashMap<(String, String), Vec<TimeSlot>> = HashMap::new();
+    for section in sections {
+        for meeting in &section.meetings {
+            if meeting.meeting_days == MeetingDay::None
+                || meeting.building.trim().is_empty()
+                || meeting.building == "TBA"
+            {
+                continue;
+            }
+
+            let slot = TimeSlot {
+                subj_course_id: section.subj_course_id.clone(),
+                meeting_type: meeting.meeting_type.clone(),
+                meeting_days: meeting.meeting_days.clone(),
+                start_hr: meeting.start_hr,
+                start_min: meeting.start_min,
+                end_hr: meeting.end_hr,
+                end_min: meeting.end_min,
+                conflict: false,
+            };
+
+            let slots = usage
+                .entry((meeting.building.clone(), meeting.room.clone()))
+                .or_default();
+            if !slots.iter().any(|other| is_same_meeting(other, &slot)) {
+                slots.push(slot);
+            }
+        }
+    }
+
+    for slots in usage.values_mut() {
+        slots.sort_by_key(|slot| slot.start_hr * 60 + slot.start_min);
+        for i in 0..slots.len() {
+            for j in (i + 1)..slots.len() {
+                if is_overlapping(&slots[i], &slots[j]) {
+                    slots[i].conflict = true;
+                    slots[j].conflict = true;
+                }
+            }
+        }
+    }
+
+    usage
+}
+
+/// Checks whether the two bookings are for the same meeting of the same course.
+///
+/// # Parameters
+/// - `a`: The first booking.
+/// - `b`: The second booking.
+///
+/// # Returns
+/// `true` if both bookings describe the same meeting, and `false` otherwise.
+fn is_same_meeting(a: &TimeSlot, b: &TimeSlot) -> bool {
+    a.subj_course_id == b.subj_course_id
+        && a.meeting_type == b.meeting_type
+        && a.meeting_days == b.meeting_days
+        && (a.start_hr, a.start_min, a.end_hr, a.end_min)
+            == (b.start_hr, b.start_min, b.end_hr, b.end_min)
+}
+
+/// Checks whether the two bookings overlap in time.
+///
+/// # Parameters
+/// - `a`: The first booking.
+/// - `b`: The second booking.
+///
+/// # Returns
+/// `true` if both bookings take place on a common day at overlapping times, and `false`
+/// otherwise.
+fn is_overlapping(a: &TimeSlot, b: &TimeSlot) -> bool {
+    let same_day = match (&a.meeting_days, &b.meeting_days) {
+        (MeetingDay::Repeated(a_days), MeetingDay::Repeated(b_days)) => {
+            a_days.iter().any(|day| b_days.contains(day))
+        }
+        (MeetingDay::OneTime(a_date), MeetingDay::OneTime(b_date)) => a_date == b_date,
+        _ => false,
+    };
+
+    let a_start = a.start_hr * 60 + a.start_min;
+    let a_end = a.end_hr * 60 + a.end_min;
+    let b_start = b.start_hr * 60 + b.start_min;
+    let b_end = b.end_hr * 60 + b.end_min;
+    same_day && a_start < b_end && b_start < a_end
+}
+
+/// Converts a course's prerequisites into a tree of requirements. Each group of course
+/// prerequisites becomes an "any of" requirement, all of which must be satisfied. Since
+/// passing any exam prerequisite satisfies all course prerequisites, exams are
+/// alternatives to the course requirements as a whole.
+///
+/// # Parameters
+/// - `info`: The prerequisites.
+///
+/// # Returns
+/// The tree of requirements, or `None` if the course has no prerequisites.
+pub fn prerequisite_tree(info: &PrerequisiteInfo) -> Option<Prereq> {
+    let mut groups = info
+        .course_prerequisites
+        .iter()
+        .filter(|group| !group.is_empty())
+        .map(|group| {
+            let mut courses = group
+                .iter()
+                .map(|c| Prereq::Course(c.subj_course_id.clone()))
+                .collect::<Vec<_>>();
+            if courses.len() == 1 {
+                courses.remove(0)
+            } else {
+                Prereq::AnyOf(courses)
+            }
+        })
+        .collect::<Vec<_>>();
+
+    let courses = match groups.len() {
+        0 => None,
+        1 => Some(groups.remove(0)),
+        _ => Some(Prereq::AllOf(groups)),
+    };
+
+    let mut alternatives = courses
+        .into_iter()
+        .chain(info.exam_prerequisites.iter().cloned().map(Prereq::Exam))
+        .collect::<Vec<_>>();
+    match alternatives.len() {
+        0 => None,
+        1 => Some(alternatives.remove(0)),
+        _ => Some(Prereq::AnyOf(alternatives)),
+    }
+}
+
+/// Looks up the sections of every given course, with at most `concurrency` lookups in
+/// flight at once.
+///
+/// # Parameters
+/// - `courses`: The courses to look up.
+/// - `concurrency`: The maximum number of lookups to run at once.
+/// - `fetch`: The function used to look up a single course, given its subject and number.
+///
+/// # Returns
+/// A map from the course (e.g., `CSE 100`) to either its sections or the reason why it
+/// couldn't be looked up. Duplicate courses are only looked up once.
+pub async fn course_info_batch<F, Fut>(
+    courses: Vec<BodyCourse>,
+    concurrency: usize,
+    fetch: F,
+) -> HashMap<String, BatchCourseResult>
+where
+    F: Fn(String, String) -> Fut,
+    Fut: Future<Output = WrapperResult<Vec<CourseSection>>>,
+{
+    let mut seen = HashMap::new();
+    for course in courses {
+        let subject = course.subject.trim().to_uppercase();
+        let number = course.number.trim().to_uppercase();
+        seen.entry(format!("{subject} {number}"))
+            .or_insert((subject, number));
+    }
+
+    stream::iter(seen)
+        .map(|(key, (subject, number))| {
+            let lookup = fetch(subject, number);
+            async move {
+                let result = match lookup.await {
+                    Ok(sections) => BatchCourseResult::Sections(sections),
+                    Err(e) => BatchCourseResult::Error {
+                        error: e.to_string(),
+                    },
+                };
+                (key, result)
+            }
+        })
+        .buffer_unordered(concurrency.max(1))
+        .collect()
+        .await
+}
+
+/// Converts the sections in a schedule to an iCalendar file, with one event per meeting.
+/// Meetings that repeat every week become a recurring event from the first day of the
+/// term to the last, and one-time meetings (e.g., final exams) become a single event.
+/// Meetings without a day are skipped.
+///
+/// The times are "floating" (i.e., they don't have a time zone); calendar applications
+/// show them in the calendar's own time zone, which is set to San Diego's.
+///
+/// # Parameters
+/// - `term`: The term.
+/// - `sections`: The sections in the schedule.
+/// - `start`: The first day that classes meet.
+/// - `end`: The last day that classes meet.
+/// - `created`: When the file is being created.
+///
+/// # Returns
+/// The iCalendar file.
+pub fn schedule_to_ics(
+    term: &str,
+    sections: &[ScheduledSection],
+    start: NaiveDate,
+    end: NaiveDate,
+    created: DateTime<Utc>,
+) -> String {
+    let mut lines = vec![
+        "BEGIN:VCALENDAR".to_owned(),
+        "VERSION:2.0".to_owned(),
+        "PRODID:-//webreg_scraper//Schedule//EN".to_owned(),
+        "CALSCALE:GREGORIAN".to_owned(),
+        "X-WR-TIMEZONE:America/Los_Angeles".to_owned(),
+    ];
+
+    for section in sections {
+        let course = format!(
+            "{} {}",
+            section.subject_code.trim(),
+            section.course_code.trim()
+        );
+        for (idx, meeting) in section.meetings.iter().enumerate() {
+            let (date, rrule) = match &meeting.meeting_days {
+                MeetingDay::Repeated(days) => {
+                    let days = WEEKDAYS
+                        .iter()
+                        .filter(|(day, _, _)| days.iter().any(|d| d == day))
+                        .collect::<Vec<_>>();
+                    // The event starts on the first day of the term that the meeting is held.
+                    let Some(first) = start
+                        .iter_days()
+                        .take_while(|date| *date <= end)
+                        .find(|date| days.iter().any(|(_, _, wd)| *wd == date.weekday()))
+                    else {
+                        continue;
+                    };
+
+                    let by_day = days
+                        .iter()
+                        .map(|(_, day, _)| *day)
+                        .collect::<Vec<_>>()
+                        .join(",");
+                    let until = end.format("%Y%m%dT235959");
+                    (
+                        first,
+                        Some(format!("RRULE:FREQ=WEEKLY;BYDAY={by_day};UNTIL={until}")),
+                    )
+                }
+                MeetingDay::OneTime(date) => {
+                    match NaiveDate::parse_from_str(date.trim(), "%Y-%m-%d") {
+                        Ok(date) => (date, None),
+                        Err(_) => continue,
+                    }
+                }
+                MeetingDay::None => continue,
+            };
+
+            let date = date.format("%Y%m%d");
+            lines.push("BEGIN:VEVENT".to_owned());
+            lines.push(format!(
+                "UID:{term}-{}-{idx}@webreg_scraper",
+                section.section_id.trim()
+            ));
+            lines.push(format!("DTSTAMP:{}", created.format("%Y%m%dT%H%M%SZ")));
+            lines.push(format!(
+                "DTSTART:{date}T{:02}{:02}00",
+                meeting.start_hr, meeting.start_min
+            ));
+            lines.push(format!(
+                "DTEND:{date}T{:02}{:02}00",
+                meeting.end_hr, meeting.end_min
+            ));
+            lines.extend(rrule);
+            lines.push(format!(
+                "SUMMARY:{}",
+                ics_escape(
+                    format!(
+                        "{course} {} ({})",
+                        meeting.meeting_type,
+                        section.section_code.trim()
+                    )
+                    .as_str()
+                )
+            ));
+
+            let location = format!("{} {}", meeting.building.trim(), meeting.room.trim());
+            if !location.trim().is_empty() {
+                lines.push(format!("LOCATION:{}", ics_escape(location.trim())));
+            }
+
+            let mut description = section.course_title.trim().to_owned();
+            if !meeting.instructors.is_empty() {
+                description.push_str(
+                    format!("\nInstructors: {}", meeting.instructors.join(", ")).as_str(),
+                );
+            }
+            lines.push(format!("DESCRIPTION:{}", ics_escape(description.as_str())));
+            lines.push("END:VEVENT".to_owned());
+        }
+    }
+
+    lines.push("END:VCALENDAR".to_owned());
+    lines
+        .iter()
+        .map(|line| ics_fold(line.as_str()))
+        .map(|line| line + "\r\n")
+        .collect()
+}
+
+/// Escapes text so that it can be used as the value of an iCalendar property.
+///
+/// # Parameters
+/// - `text`: The text.
+///
+/// # Returns
+/// The escaped text.
+fn ics_escape(text: &str) -> String {
+    text.replace('\\', "\\\\")
+        .replace(';', "\\;")
+        .replace(',', "\\,")
+        .replace('\n', "\\n")
+}
+
+/// Folds a line of an iCalendar file so that no line is longer than 75 bytes, as the
+/// format requires.
+///
+/// # Parameters
+/// - `line`: The line.
+///
+/// # Returns
+/// The folded line.
+fn ics_fold(line: &str) -> String {
+    let mut folded = String::with_capacity(line.len());
+    let mut line_len = 0;
+    for c in line.chars() {
+        if line_len + c.len_utf8() > 75 {
+            folded.push_str("\r\n ");
+            line_len = 1;
+        }
+
+        folded.push(c);
+        line_len += c.len_utf8();
+    }
+
+    folded
+}
+
+/// Writes the given sections as CSV, with one row per meeting. Each row has the same
+/// columns as the tracker's CSV output (minus the time), followed by the meeting itself.
+/// Sections without any meetings are written as a single row with an empty meeting.
+///
+/// # Parameters
+/// - `sections`: The sections.
+/// - `writer`: Where to write the CSV to.
+///
+/// # Returns
+/// An error if the CSV couldn't be written.
+pub fn export_course_sections_csv(
+    sections: &[CourseSection],
+    mut writer: impl Write,
+) -> io::Result<()> {
+    writeln!(
+        writer,
+        "subj_course_id,sec_code,sec_id,prof,available,waitlist,total,enrolled_ct,meeting"
+    )?;
+
+    for section in sections {
+        let meetings = section
+            .meetings
+            .iter()
+            .map(|meeting| meeting.to_string().trim().to_owned())
+            .collect::<Vec<_>>();
+        let meetings = if meetings.is_empty() {
+            vec![String::new()]
+        } else {
+            meetings
+        };
+
+        for meeting in meetings {
+            writeln!(
+                writer,
+                "{},{},{},{},{},{},{},{},{}",
+                csv_field(section.subj_course_id.as_str()),
+                csv_field(section.section_code.as_str()),
+                csv_field(section.section_id.as_str()),
+                csv_field(section.all_instructors.join(" & ").as_str()),
+                section.available_seats,
+                section.waitlist_ct,
+                section.total_seats,
+                section.enrolled_ct,
+                csv_field(meeting.as_str()),
+            )?;
+        }
+    }
+
+    Ok(())
+}
+
+/// Quotes a CSV field if it contains a comma, quote, or line break.
+///
+/// # Parameters
+/// - `field`: The field.
+///
+/// # Returns
+/// The field, quoted if needed.
+fn csv_field(field: &str) -> Cow<'_, str> {
+    if field.contains([',', '"', '\n', '\r']) {
+        Cow::Owned(format!("\"{}\"", field.replace('"', "\"\"")))
+    } else {
+        Cow::Borrowed(field)
+    }
+}
+
+/// Looks up a single section of a course by its section ID.
+///
+/// # Parameters
+/// - `subject`: The course's subject (e.g., `CSE`).
+/// - `number`: The course's number (e.g., `100`).
+/// - `section_id`: The section ID (e.g., `123456`).
+/// - `fetch`: The function used to look up the course's sections, given its subject and
+///   number.
+///
+/// # Returns
+/// The section, or `None` if the course doesn't have a section with the given ID.
+pub async fn section_by_id<F, Fut>(
+    subject: &str,
+    number: &str,
+    section_id: &str,
+    fetch: F,
+) -> WrapperResult<Option<CourseSection>>
+where
+    F: FnOnce(String, String) -> Fut,
+    Fut: Future<Output = WrapperResult<Vec<CourseSection>>>,
+{
+    let sections = fetch(subject.trim().to_owned(), number.trim().to_owned()).await?;
+    Ok(sections
+        .into_iter()
+        .find(|section| section.section_id == section_id.trim()))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use webweg::types::{CoursePrerequisite, EnrollmentStatus, Meeting, WrapperError};
+
+    fn make_section(subj_course_id: &str, section_id: &str, days: &[&str]) -> CourseSection {
+        CourseSection {
+            subj_course_id: subj_course_id.to_owned(),
+            section_id: section_id.to_owned(),
+            section_code: "A00".to_owned(),
+            all_instructors: vec![],
+            available_seats: 0,
+            enrolled_ct: 0,
+            total_seats: 0,
+            waitlist_ct: 0,
+            meetings: vec![Meeting {
+                meeting_type: "LE".to_owned(),
+                meeting_days: MeetingDay::Repeated(days.iter().map(|d| d.to_string()).collect()),
+                start_hr: 10,
+                start_min: 0,
+                end_hr: 10,
+                end_min: 50,
+                building: "CENTR".to_owned(),
+                room: "115".to_owned(),
+                instructors: vec![],
+            }],
+            is_visible: true,
+        }
+    }
+
+    #[test]
+    fn test_parse_grade_option() {
+        assert!(matches!(parse_grade_option(None), Ok(GradeOption::L)));
+        assert!(matches!(parse_grade_option(Some("L")), Ok(GradeOption::L)));
+        assert!(matches!(parse_grade_option(Some("p")), Ok(GradeOption::P)));
+        assert!(matches!(
+            parse_grade_option(Some(" S ")),
+            Ok(GradeOption::S)
+        ));
+
+        for invalid in ["", "X", "letter", "PS"] {
+            let e = parse_grade_option(Some(invalid)).unwrap_err();
+            assert!(e.contains(format!("'{invalid}'").as_str()), "{e}");
+        }
+
+        let body = BodyAddInfo {
+            section_id: "123456".to_owned(),
+            grading_option: Some("Z".to_owned()),
+            unit_count: Some(4),
+            validate: None,
+        };
+        assert!(build_add_section_object(&body).is_err());
+        assert!(parse_grade_option_unit_count(&Some("P".to_owned()), Some(2)).is_ok());
+    }
+
+    #[test]
+    fn test_room_usage_conflict() {
+        let sections = [
+            make_section("CSE 100", "000001", &["M", "W", "F"]),
+            make_section("MATH 20C", "000002", &["M"]),
+        ];
+
+        let usage = room_usage(&sections);
+        let slots = &usage[&("CENTR".to_owned(), "115".to_owned())];
+        assert_eq!(2, slots.len());
+        assert!(slots.iter().all(|slot| slot.conflict));
+    }
+
+    #[test]
+    fn test_room_usage_shared_meeting() {
+        let sections = [
+            make_section("CSE 100", "000001", &["M", "W", "F"]),
+            make_section("CSE 100", "000002", &["M", "W", "F"]),
+            make_section("MATH 20C", "000003", &["Tu", "Th"]),
+        ];
+
+        let usage = room_usage(&sections);
+        let slots = &usage[&("CENTR".to_owned(), "115".to_owned())];
+        assert_eq!(2, slots.len());
+        assert!(slots.iter().all(|slot| !slot.conflict));
+    }
+
+    #[test]
+    fn test_prerequisite_tree() {
+        let mut info = PrerequisiteInfo {
+            course_prerequisites: vec![
+                vec![CoursePrerequisite::new(
+                    "CSE 12",
+                    "Basic Data Struct & OO Design",
+                )],
+                vec![
+                    CoursePrerequisite::new("MATH 18", "Linear Algebra"),
+                    CoursePrerequisite::new("MATH 31AH", "Honors Linear Algebra"),
+                ],
+            ],
+            exam_prerequisites: vec![],
+        };
+
+        let tree = prerequisite_tree(&info).unwrap();
+        assert_eq!(
+            Prereq::AllOf(vec![
+                Prereq::Course("CSE 12".to_owned()),
+                Prereq::AnyOf(vec![
+                    Prereq::Course("MATH 18".to_owned()),
+                    Prereq::Course("MATH 31AH".to_owned()),
+                ]),
+            ]),
+            tree
+        );
+        assert_eq!("CSE 12 and (MATH 18 or MATH 31AH)", tree.to_string());
+
+        info.exam_prerequisites
+            .push("Math Placement Exam".to_owned());
+        assert_eq!(
+            "(CSE 12 and (MATH 18 or MATH 31AH)) or Math Placement Exam",
+            prerequisite_tree(&info).unwrap().to_string()
+        );
+
+        info.course_prerequisites.clear();
+        info.exam_prerequisites.clear();
+        assert_eq!(None, prerequisite_tree(&info));
+    }
+
+    #[tokio::test]
+    async fn test_course_info_batch() {
+        let courses = vec![
+            BodyCourse {
+                subject: "CSE".to_owned(),
+                number: "100".to_owned(),
+            },
+            BodyCourse {
+                subject: "math".to_owned(),
+                number: " 20c".to_owned(),
+            },
+            BodyCourse {
+                subject: "CSE".to_owned(),
+                number: "100".to_owned(),
+            },
+        ];
+
+        let results = course_info_batch(courses, 2, |subject, number| async move {
+            if subject == "CSE" {
+                Ok(vec![make_section(
+                    format!("{subject} {number}").as_str(),
+                    "123456",
+                    &["M"],
+                )])
+            } else {
+                Err(WrapperError::WebRegError("Course not found.".to_owned()))
+            }
+        })
+        .await;
+
+        assert_eq!(2, results.len());
+        match &results["CSE 100"] {
+            BatchCourseResult::Sections(sections) => {
+                assert_eq!(1, sections.len());
+                assert_eq!("123456", sections[0].section_id);
+            }
+            BatchCourseResult::Error { error } => panic!("unexpected error: {error}"),
+        }
+        assert!(matches!(
+            results["MATH 20C"],
+            BatchCourseResult::Error { .. }
+        ));
+    }
+
+    #[test]
+    fn test_schedule_to_ics() {
+        let meeting = |meeting_type: &str, meeting_days: MeetingDay| Meeting {
+            meeting_type: meeting_type.to_owned(),
+            meeting_days,
+            start_hr: 9,
+            start_min: 0,
+            end_hr: 9,
+            end_min: 50,
+            building: "CENTR".to_owned(),
+            room: "115".to_owned(),
+            instructors: vec!["Doe, Jane".to_owned()],
+        };
+        let sections = vec![ScheduledSection {
+            section_id: "123456".to_owned(),
+            subject_code: "CSE".to_owned(),
+            course_code: "100".to_owned(),
+            course_title: "Advanced Data Structure".to_owned(),
+            section_code: "A01".to_owned(),
+            section_capacity: 100,
+            enrolled_count: 90,
+            available_seats: 10,
+            grade_option: "L".to_owned(),
+            all_instructors: vec!["Doe, Jane".to_owned()],
+            units: 4,
+            enrolled_status: EnrollmentStatus::Enrolled,
+            waitlist_ct: 0,
+            meetings: vec![
+                meeting(
+                    "LE",
+                    MeetingDay::Repeated(vec!["Tu".to_owned(), "Th".to_owned()]),
+                ),
+                meeting("FI", MeetingDay::OneTime("2024-12-10".to_owned())),
+                meeting("LA", MeetingDay::None),
+            ],
+        }];
+
+        let created = DateTime::from_timestamp(1_700_000_000, 0).unwrap();
+        // The term starts on a Thursday.
+        let start = NaiveDate::from_ymd_opt(2024, 9, 26).unwrap();
+        let end = NaiveDate::from_ymd_opt(2024, 12, 6).unwrap();
+        let ics = schedule_to_ics("FA24", &sections, start, end, created);
+
+        assert!(ics.starts_with("BEGIN:VCALENDAR\r\n"));
+        assert!(ics.ends_with("END:VCALENDAR\r\n"));
+        assert!(ics.lines().all(|line| line.len() <= 76));
+        // The meeting without a day doesn't have an event.
+        assert_eq!(2, ics.matches("BEGIN:VEVENT").count());
+
+        let events = ics.split("BEGIN:VEVENT").skip(1).collect::<Vec<_>>();
+        assert!(events[0].contains("UID:FA24-123456-0@webreg_scraper\r\n"));
+        assert!(events[0].contains("DTSTART:20240926T090000\r\n"));
+        assert!(events[0].contains("DTEND:20240926T095000\r\n"));
+        assert!(events[0].contains("RRULE:FREQ=WEEKLY;BYDAY=TU,TH;UNTIL=20241206T235959\r\n"));
+        assert!(events[0].contains("SUMMARY:CSE 100 LE (A01)\r\n"));
+        assert!(events[0].contains("LOCATION:CENTR 115\r\n"));
+        assert!(events[0]
+            .contains("DESCRIPTION:Advanced Data Structure\\nInstructors: Doe\\, Jane\r\n"));
+        assert!(events[0].contains("DTSTAMP:20231114T221320Z\r\n"));
+
+        assert!(events[1].contains("DTSTART:20241210T090000\r\n"));
+        assert!(events[1].contains("SUMMARY:CSE 100 FI (A01)\r\n"));
+        assert!(!events[1].contains("RRULE"));
+    }
+
+    #[test]
+    fn test_export_course_sections_csv() {
+        let mut section = make_section("CSE 100", "123456", &["M", "W", "F"]);
+        section.all_instructors = vec!["Doe, Jane".to_owned(), "Staff".to_owned()];
+        section.available_seats = 3;
+        section.waitlist_ct = 1;
+        section.total_seats = 100;
+        section.enrolled_ct = 97;
+        section.meetings.push(Meeting {
+            meeting_type: "FI".to_owned(),
+            meeting_days: MeetingDay::OneTime("2024-12-10".to_owned()),
+            start_hr: 8,
+            start_min: 0,
+            end_hr: 10,
+            end_min: 59,
+            building: "WLH".to_owned(),
+            room: "2001".to_owned(),
+            instructors: vec![],
+        });
+        let mut empty = make_section("CSE 100", "654321", &[]);
+        empty.meetings.clear();
+
+        let mut csv = vec![];
+        export_course_sections_csv(&[section, empty], &mut csv).unwrap();
+        let csv = String::from_utf8(csv).unwrap();
+        assert_eq!(
+            vec![
+                "subj_course_id,sec_code,sec_id,prof,available,waitlist,total,enrolled_ct,meeting",
+                "CSE 100,A00,123456,\"Doe, Jane & Staff\",3,1,100,97,[LE] MWF at 10:00 - 10:50 in CENTR 115",
+                "CSE 100,A00,123456,\"Doe, Jane & Staff\",3,1,100,97,[FI] 2024-12-10 at 8:00 - 10:59 in WLH 2001",
+                "CSE 100,A00,654321,,0,0,0,0,",
+            ],
+            csv.lines().collect::<Vec<_>>()
+        );
+    }
+
+    #[test]
+    fn test_csv_field() {
+        assert_eq!("CSE 100", csv_field("CSE 100"));
+        assert_eq!("\"Doe, Jane\"", csv_field("Doe, Jane"));
+        assert_eq!("\"a \"\"b\"\"\"", csv_field("a \"b\""));
+    }
+
+    #[test]
+    fn test_ics_fold() {
+        let line = format!("DESCRIPTION:{}", "a".repeat(100));
+        let folded = ics_fold(line.as_str());
+        let lines = folded.split("\r\n").collect::<Vec<_>>();
+        assert_eq!(2, lines.len());
+        assert_eq!(75, lines[0].len());
+        assert!(lines[1].starts_with(' '));
+        assert_eq!(line, lines.concat().replacen(' ', "", 1));
+    }
+
+    #[tokio::test]
+    async fn test_section_by_id() {
+        let fetch = |subject: String, number: String| async move {
+            let course = format!("{subject} {number}");
+            Ok(vec![
+                make_section(course.as_str(), "111111", &["M"]),
+                make_section(course.as_str(), "222222", &["W"]),
+            ])
+        };
+
+        let section = section_by_id("CSE", "100", "222222", fetch)
+            .await
+            .unwrap()
+            .unwrap();
+        assert_eq!("222222", section.section_id);
+        assert_eq!("CSE 100", section.subj_course_id);
+
+        assert!(section_by_id("CSE", "100", "333333", fetch)
+            .await
+            .unwrap()
+            .is_none());
+
+        let failed = section_by_id("CSE", "100", "111111", |_, _| async {
+            Err(WrapperError::WebRegError("Course not found.".to_owned()))
+        })
+        .await;
+        assert!(failed.is_err());
+    }
 }
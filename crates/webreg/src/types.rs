@@ -1,18 +1,74 @@
 use std::collections::{HashMap, VecDeque};
+use std::future::Future;
+use std::hash::Hash;
+use std::path::{Path, PathBuf};
 use std::sync::atomic::{AtomicBool, AtomicUsize, Ordering};
-use std::sync::{Arc, Mutex};
+#[cfg(feature = "postgres")]
+use std::sync::OnceLock;
+use std::sync::{Arc, Mutex, RwLock};
+use std::time::{Duration, Instant};
 
 use reqwest::Client;
 use serde::{Deserialize, Serialize};
+use tracing::{error, info, warn};
+use webweg::types::{CourseSection, WrapperError};
 use webweg::wrapper::input_types::{CourseLevelFilter, SearchRequestBuilder};
+use webweg::wrapper::wrapper_builder::WebRegWrapperBuilder;
 use webweg::wrapper::WebRegWrapper;
 
+use crate::scraper::tracker::{
+    BASE_DELAY_FOR_SESSION_COOKIE, MAX_NUM_LOGIN_FAILURES, MAX_NUM_REGISTER,
+    MAX_NUM_SEARCH_REQUESTS,
+};
+
 const MAX_RECENT_REQUESTS: usize = 2000;
 
+/// The version of the data returned by the API and written by the tracker. This should be
+/// bumped whenever the shape of the serialized course data or the tracker's output rows
+/// changes, so that anyone consuming this data can detect the change.
+pub const SCHEMA_VERSION: u32 = 1;
+
+/// The estimated number of requests per minute, across all terms, above which a warning is
+/// logged on startup.
+const WARN_REQUESTS_PER_MINUTE: f64 = 30.0;
+/// The approximate number of courses returned by a search query over all departments.
+const APPROX_NUM_COURSES_ALL: usize = 3000;
+/// The approximate number of courses returned by a search query per department.
+const APPROX_NUM_COURSES_PER_DEPT: usize = 40;
+/// The number of most recent requests considered when adapting the cooldown.
+const ADAPTIVE_COOLDOWN_WINDOW: usize = 10;
+/// The factor by which the cooldown is increased when WebReg appears to be under load.
+const ADAPTIVE_COOLDOWN_INCREASE: f64 = 1.5;
+/// The factor by which the cooldown is decreased when WebReg appears to be healthy.
+const ADAPTIVE_COOLDOWN_DECREASE: f64 = 0.9;
+/// The default average request time, in milliseconds, above which WebReg is considered to
+/// be under load.
+const DEFAULT_LATENCY_THRESHOLD_MS: usize = 2000;
+/// The factor that a fixed cooldown is multiplied by after a request fails.
+const FAILURE_BACKOFF_FACTOR: f64 = 2.0;
+/// The factor that a backed-off cooldown is multiplied by after requests succeed, until it
+/// returns to the configured cooldown.
+const FAILURE_BACKOFF_DECAY: f64 = 0.5;
+/// The largest multiple of the configured cooldown that backing off can reach.
+const MAX_FAILURE_BACKOFF_MULTIPLIER: f64 = 8.0;
+/// The default maximum size of a response body that will be read, in megabytes.
+const DEFAULT_MAX_BODY_SIZE_MB: u64 = 32;
+/// The default amount of time, in seconds, that a request to WebReg can take.
+const DEFAULT_REQUEST_TIMEOUT_SECS: u64 = 30;
+/// The default amount of time, in seconds, that a cached response is served for.
+const DEFAULT_CACHE_TTL_SECS: u64 = 60;
+/// The default path to the database holding the API keys.
+#[cfg(feature = "auth")]
+const DEFAULT_AUTH_DB: &str = "auth.db";
+
 /// A structure that represents the current state of all wrappers.
 pub struct WrapperState {
-    /// A map containing all active scrapers, grouped by term.
-    pub all_terms: WrapperMap,
+    /// A map containing all active scrapers, grouped by term. This is replaced when the
+    /// configuration is reloaded; use `terms` or `term` to read it.
+    pub all_terms: RwLock<WrapperMap>,
+    /// Notified when the terms have been replaced, so that the tracker can restart with
+    /// the new terms.
+    pub reload_notify: tokio::sync::Notify,
     /// The stop flag; i.e., the flag that indicates whether the scraper should be stopped.
     pub stop_flag: AtomicBool,
     /// Whether the scrapers are running at this moment.
@@ -23,14 +79,47 @@ pub struct WrapperState {
     pub wrapper: WebRegWrapper,
     /// A wrapper to be used to serve requests that involve other cookies.
     pub c_wrapper: WebRegWrapper,
+    /// The settings used to create wrappers, for requests that need a wrapper of their own.
+    pub wrapper_settings: WrapperSettings,
+    /// The generation of `wrapper`'s session cookies; this is incremented every time the
+    /// cookies are replaced. The tracker holds a read guard while making requests with the
+    /// cookies, and a write guard is held while the cookies are replaced and registered, so
+    /// that no request is made with cookies that are only partially set up.
+    pub cookie_generation: tokio::sync::RwLock<u64>,
     /// The address for which the endpoints specified in this application is made
     /// available for other applications to use.
     pub api_base_endpoint: AddressPortInfo,
-    /// The cookie server.
-    pub cookie_server: AddressPortInfo,
+    /// The cookie servers, in the order that they should be tried.
+    pub cookie_servers: Vec<AddressPortInfo>,
+    /// The index, in `cookie_servers`, of the cookie server that most recently provided
+    /// session cookies.
+    pub current_cookie_server: AtomicUsize,
+    /// The retention policy for the tracker's output files, if any.
+    pub retention: Option<ConfigRetention>,
+    /// The maximum size of a response body that will be read, in bytes.
+    pub max_body_size: usize,
+    /// The number of times the initial login should be retried before falling back to the
+    /// usual exponential backoff.
+    pub initial_login_attempts: i32,
+    /// The limits that decide when the tracker gives up on a set of session cookies.
+    pub thresholds: TrackerThresholds,
+    /// The origins that browsers are allowed to make cross-origin requests to the API from.
+    pub allowed_origins: Vec<String>,
     /// The authentication manager, to be used by the server.
     #[cfg(feature = "auth")]
     pub auth_manager: basicauth::AuthManager,
+    /// The cache for the `course_info` endpoint, if responses should be cached.
+    pub course_info_cache: Option<CourseInfoCache>,
+    /// The rate limiter for requests made with each API key, if requests are rate-limited.
+    #[cfg(feature = "auth")]
+    pub rate_limiter: Option<RateLimiter>,
+    /// The connection string for the PostgreSQL database that scraped data should be
+    /// written to, if any.
+    #[cfg(feature = "postgres")]
+    pub postgres_url: Option<String>,
+    /// The sink for the PostgreSQL database, once connected.
+    #[cfg(feature = "postgres")]
+    pub postgres: OnceLock<crate::scraper::postgres::PostgresSink>,
 }
 
 impl WrapperState {
@@ -38,64 +127,157 @@ impl WrapperState {
     ///
     /// # Parameter
     /// - `config`: The configuration data.
+    /// - `webreg_client`: The client that the wrappers should use to make requests to
+    ///   WebReg.
     ///
     /// # Returns
     /// The wrapper state.
-    pub fn new(config: ConfigScraper) -> Self {
-        let term_info: WrapperMap = config
+    pub fn new(config: ConfigScraper, webreg_client: webweg_reqwest::Client) -> Self {
+        let max_body_size = config.max_body_size();
+        let thresholds = config.thresholds();
+        let output_dir = config
+            .output_dir
+            .clone()
+            .unwrap_or_else(|| PathBuf::from("."));
+        let sink = config.sink.unwrap_or_default();
+        let wrapper_settings = config.wrapper_settings(&webreg_client);
+        let wrapper = wrapper_settings
+            .wrapper_builder()
+            .with_cookies("To be loaded later")
+            .try_build_wrapper()
+            .unwrap();
+        let c_wrapper = wrapper_settings
+            .wrapper_builder()
+            .with_cookies("To be determined by the user's cookies.")
+            .should_close_after_request(true)
+            .try_build_wrapper()
+            .unwrap();
+        let term_info = config
             .wrapper_data
             .into_iter()
-            .map(|data| TermInfo {
-                term: data.term,
-                cooldown: data.cooldown,
-                search_query: data
-                    .search_query
-                    .into_iter()
-                    .map(|query| {
-                        let mut parsed = SearchRequestBuilder::new();
-                        for level in query.levels {
-                            parsed = match level.as_str() {
-                                "g" => parsed.filter_courses_by(CourseLevelFilter::Graduate),
-                                "u" => parsed.filter_courses_by(CourseLevelFilter::UpperDivision),
-                                "l" => parsed.filter_courses_by(CourseLevelFilter::LowerDivision),
-                                _ => continue,
-                            };
-                        }
+            .map(|data| TermInfo::from_config(data, &output_dir, sink))
+            .collect();
 
-                        for dept in query.departments {
-                            parsed = parsed.add_department(dept);
-                        }
-                        parsed
-                    })
-                    .collect(),
-                tracker: StatTracker {
-                    recent_requests: Default::default(),
-                    num_requests: Default::default(),
-                    total_time_spent: Default::default(),
-                },
+        let builder = Self::builder()
+            .with_terms(term_info)
+            .with_wrapper_settings(wrapper_settings)
+            .with_client(Client::default())
+            .with_wrapper(wrapper)
+            .with_cookie_wrapper(c_wrapper)
+            .with_api_base_endpoint(config.api_base_endpoint)
+            .with_cookie_servers(config.cookie_server.into_vec())
+            .with_retention(config.retention)
+            .with_max_body_size(max_body_size)
+            .with_initial_login_attempts(
+                config
+                    .initial_login_attempts
+                    .unwrap_or(MAX_NUM_LOGIN_FAILURES),
+            )
+            .with_thresholds(thresholds)
+            .with_allowed_origins(config.allowed_origins.unwrap_or_default())
+            .with_course_info_cache(config.course_info_cache);
+
+        #[cfg(feature = "auth")]
+        let builder = builder
+            .with_auth_manager(basicauth::AuthManager::new(
+                config.auth_db.as_deref().unwrap_or(DEFAULT_AUTH_DB),
+            ))
+            .with_rate_limit(config.rate_limit);
+        #[cfg(feature = "postgres")]
+        let builder = builder.with_postgres_url(config.postgres_url);
+
+        builder.build()
+    }
+
+    /// Gets all terms that are currently being tracked.
+    ///
+    /// # Returns
+    /// A snapshot of the terms; this isn't updated if the configuration is reloaded.
+    pub fn terms(&self) -> WrapperMap {
+        self.all_terms.read().unwrap().clone()
+    }
+
+    /// Gets a term that is currently being tracked.
+    ///
+    /// # Parameters
+    /// - `term`: The term.
+    ///
+    /// # Returns
+    /// The term information, or `None` if the term isn't being tracked.
+    pub fn term(&self, term: &str) -> Option<Arc<TermInfo>> {
+        self.all_terms.read().unwrap().get(term).cloned()
+    }
+
+    /// Replaces the tracked terms with the ones in the given configuration. Terms that are
+    /// in both the old and new configuration keep their stats (and stay paused, if they
+    /// were); everything else about them (e.g., the search query and cooldown) is taken
+    /// from the new configuration. The tracker is notified so that it can restart with the
+    /// new terms.
+    ///
+    /// Only the wrapper data (and the output directory and sink that apply to it) are
+    /// reloaded; changing anything else requires a restart.
+    ///
+    /// # Parameters
+    /// - `config`: The new configuration.
+    ///
+    /// # Returns
+    /// The terms that were added and the terms that were removed, respectively.
+    pub fn reload_terms(&self, config: ConfigScraper) -> (Vec<String>, Vec<String>) {
+        let output_dir = config.output_dir.unwrap_or_else(|| PathBuf::from("."));
+        let sink = config.sink.unwrap_or_default();
+        let mut all_terms = self.all_terms.write().unwrap();
+        let new_terms = config
+            .wrapper_data
+            .into_iter()
+            .map(|data| {
+                let mut info = TermInfo::from_config(data, &output_dir, sink);
+                if let Some(old) = all_terms.get(&info.term) {
+                    info.tracker = old.tracker.clone();
+                    info.set_paused(old.is_paused());
+                }
+
+                (info.term.clone(), Arc::new(info))
             })
-            .map(|data| (data.term.to_owned(), Arc::new(data)))
-            .collect();
+            .collect::<WrapperMap>();
 
-        Self {
-            all_terms: term_info,
-            stop_flag: AtomicBool::from(false),
-            is_running: AtomicBool::from(false),
-            client: Default::default(),
-            wrapper: WebRegWrapper::builder()
-                .with_cookies("To be loaded later")
-                .try_build_wrapper()
-                .unwrap(),
-            c_wrapper: WebRegWrapper::builder()
-                .with_cookies("To be determined by the user's cookies.")
-                .should_close_after_request(true)
-                .try_build_wrapper()
-                .unwrap(),
-            api_base_endpoint: config.api_base_endpoint,
-            cookie_server: config.cookie_server,
-            #[cfg(feature = "auth")]
-            auth_manager: basicauth::AuthManager::new("auth.db"),
-        }
+        let mut added = new_terms
+            .keys()
+            .filter(|term| !all_terms.contains_key(*term))
+            .cloned()
+            .collect::<Vec<_>>();
+        let mut removed = all_terms
+            .keys()
+            .filter(|term| !new_terms.contains_key(*term))
+            .cloned()
+            .collect::<Vec<_>>();
+        added.sort();
+        removed.sort();
+
+        *all_terms = new_terms;
+        drop(all_terms);
+        self.reload_notify.notify_one();
+        (added, removed)
+    }
+
+    /// Gets the cookie server that most recently provided session cookies, or the first
+    /// cookie server if none has yet.
+    ///
+    /// # Returns
+    /// The cookie server, or `None` if no cookie servers are configured.
+    pub fn cookie_server(&self) -> Option<&AddressPortInfo> {
+        let idx = self.current_cookie_server.load(Ordering::SeqCst);
+        self.cookie_servers
+            .get(idx)
+            .or_else(|| self.cookie_servers.first())
+    }
+
+    /// Creates a builder for a `WrapperState`. This is mostly useful for testing, where the
+    /// wrappers or authentication manager might need to be replaced; otherwise, use `new`.
+    ///
+    /// # Returns
+    /// The builder.
+    pub fn builder() -> WrapperStateBuilder {
+        WrapperStateBuilder::default()
     }
 
     /// Gets the current status of the stop flag.
@@ -126,6 +308,356 @@ impl WrapperState {
 
 pub type WrapperMap = HashMap<String, Arc<TermInfo>>;
 
+/// A builder for a `WrapperState`. Anything that isn't specified uses a default value.
+#[derive(Default)]
+pub struct WrapperStateBuilder {
+    terms: Vec<TermInfo>,
+    client: Option<Client>,
+    wrapper: Option<WebRegWrapper>,
+    c_wrapper: Option<WebRegWrapper>,
+    wrapper_settings: Option<WrapperSettings>,
+    api_base_endpoint: AddressPortInfo,
+    cookie_servers: Vec<AddressPortInfo>,
+    retention: Option<ConfigRetention>,
+    max_body_size: Option<usize>,
+    initial_login_attempts: Option<i32>,
+    thresholds: TrackerThresholds,
+    allowed_origins: Vec<String>,
+    course_info_cache: Option<ConfigCache>,
+    #[cfg(feature = "auth")]
+    auth_manager: Option<basicauth::AuthManager>,
+    #[cfg(feature = "auth")]
+    rate_limit: Option<ConfigRateLimit>,
+    #[cfg(feature = "postgres")]
+    postgres_url: Option<String>,
+}
+
+impl WrapperStateBuilder {
+    /// Sets the terms that the scraper should track.
+    ///
+    /// # Parameters
+    /// - `terms`: The terms.
+    ///
+    /// # Returns
+    /// The builder.
+    pub fn with_terms(mut self, terms: Vec<TermInfo>) -> Self {
+        self.terms = terms;
+        self
+    }
+
+    /// Sets the client that can be used to make requests.
+    ///
+    /// # Parameters
+    /// - `client`: The client.
+    ///
+    /// # Returns
+    /// The builder.
+    pub fn with_client(mut self, client: Client) -> Self {
+        self.client = Some(client);
+        self
+    }
+
+    /// Sets the wrapper that can be used to make requests to WebReg.
+    ///
+    /// # Parameters
+    /// - `wrapper`: The wrapper.
+    ///
+    /// # Returns
+    /// The builder.
+    pub fn with_wrapper(mut self, wrapper: WebRegWrapper) -> Self {
+        self.wrapper = Some(wrapper);
+        self
+    }
+
+    /// Sets the wrapper that can be used to serve requests that involve other cookies. This
+    /// wrapper should close the connection after every request.
+    ///
+    /// # Parameters
+    /// - `wrapper`: The wrapper.
+    ///
+    /// # Returns
+    /// The builder.
+    pub fn with_cookie_wrapper(mut self, wrapper: WebRegWrapper) -> Self {
+        self.c_wrapper = Some(wrapper);
+        self
+    }
+
+    /// Sets the settings used to create wrappers.
+    ///
+    /// # Parameters
+    /// - `settings`: The settings.
+    ///
+    /// # Returns
+    /// The builder.
+    pub fn with_wrapper_settings(mut self, settings: WrapperSettings) -> Self {
+        self.wrapper_settings = Some(settings);
+        self
+    }
+
+    /// Sets the address that the API is made available on.
+    ///
+    /// # Parameters
+    /// - `api_base_endpoint`: The address and port.
+    ///
+    /// # Returns
+    /// The builder.
+    pub fn with_api_base_endpoint(mut self, api_base_endpoint: AddressPortInfo) -> Self {
+        self.api_base_endpoint = api_base_endpoint;
+        self
+    }
+
+    /// Sets the cookie servers, in the order that they should be tried.
+    ///
+    /// # Parameters
+    /// - `cookie_servers`: The addresses and ports of the cookie servers.
+    ///
+    /// # Returns
+    /// The builder.
+    pub fn with_cookie_servers(mut self, cookie_servers: Vec<AddressPortInfo>) -> Self {
+        self.cookie_servers = cookie_servers;
+        self
+    }
+
+    /// Sets the retention policy for the tracker's output files.
+    ///
+    /// # Parameters
+    /// - `retention`: The retention policy, if any.
+    ///
+    /// # Returns
+    /// The builder.
+    pub fn with_retention(mut self, retention: Option<ConfigRetention>) -> Self {
+        self.retention = retention;
+        self
+    }
+
+    /// Sets the maximum size of a response body that will be read.
+    ///
+    /// # Parameters
+    /// - `max_body_size`: The maximum size, in bytes.
+    ///
+    /// # Returns
+    /// The builder.
+    pub fn with_max_body_size(mut self, max_body_size: usize) -> Self {
+        self.max_body_size = Some(max_body_size);
+        self
+    }
+
+    /// Sets the number of times the initial login should be retried.
+    ///
+    /// # Parameters
+    /// - `attempts`: The number of attempts.
+    ///
+    /// # Returns
+    /// The builder.
+    pub fn with_initial_login_attempts(mut self, attempts: i32) -> Self {
+        self.initial_login_attempts = Some(attempts);
+        self
+    }
+
+    /// Sets the limits that decide when the tracker gives up on a set of session cookies.
+    ///
+    /// # Parameters
+    /// - `thresholds`: The limits.
+    ///
+    /// # Returns
+    /// The builder.
+    pub fn with_thresholds(mut self, thresholds: TrackerThresholds) -> Self {
+        self.thresholds = thresholds;
+        self
+    }
+
+    /// Sets the origins that browsers are allowed to make cross-origin requests to the API
+    /// from.
+    ///
+    /// # Parameters
+    /// - `origins`: The origins (e.g., `https://example.com`), or `*` for any origin.
+    ///
+    /// # Returns
+    /// The builder.
+    pub fn with_allowed_origins(mut self, origins: Vec<String>) -> Self {
+        self.allowed_origins = origins;
+        self
+    }
+
+    /// Sets how responses from the `course_info` endpoint should be cached.
+    ///
+    /// # Parameters
+    /// - `cache`: The cache settings, if responses should be cached.
+    ///
+    /// # Returns
+    /// The builder.
+    pub fn with_course_info_cache(mut self, cache: Option<ConfigCache>) -> Self {
+        self.course_info_cache = cache;
+        self
+    }
+
+    /// Sets the authentication manager, to be used by the server.
+    ///
+    /// # Parameters
+    /// - `auth_manager`: The authentication manager.
+    ///
+    /// # Returns
+    /// The builder.
+    #[cfg(feature = "auth")]
+    pub fn with_auth_manager(mut self, auth_manager: basicauth::AuthManager) -> Self {
+        self.auth_manager = Some(auth_manager);
+        self
+    }
+
+    /// Sets how many requests can be made with each API key.
+    ///
+    /// # Parameters
+    /// - `rate_limit`: The rate limit, if requests should be rate-limited.
+    ///
+    /// # Returns
+    /// The builder.
+    #[cfg(feature = "auth")]
+    pub fn with_rate_limit(mut self, rate_limit: Option<ConfigRateLimit>) -> Self {
+        self.rate_limit = rate_limit;
+        self
+    }
+
+    /// Sets the connection string for the PostgreSQL database that scraped data should be
+    /// written to.
+    ///
+    /// # Parameters
+    /// - `postgres_url`: The connection string, if any.
+    ///
+    /// # Returns
+    /// The builder.
+    #[cfg(feature = "postgres")]
+    pub fn with_postgres_url(mut self, postgres_url: Option<String>) -> Self {
+        self.postgres_url = postgres_url;
+        self
+    }
+
+    /// Builds the `WrapperState`.
+    ///
+    /// # Returns
+    /// The wrapper state.
+    pub fn build(self) -> WrapperState {
+        WrapperState {
+            all_terms: RwLock::new(
+                self.terms
+                    .into_iter()
+                    .map(|term| (term.term.to_owned(), Arc::new(term)))
+                    .collect(),
+            ),
+            reload_notify: tokio::sync::Notify::new(),
+            stop_flag: AtomicBool::from(false),
+            is_running: AtomicBool::from(false),
+            client: self.client.unwrap_or_default(),
+            wrapper: self.wrapper.unwrap_or_else(|| {
+                WebRegWrapper::builder()
+                    .with_cookies("To be loaded later")
+                    .try_build_wrapper()
+                    .unwrap()
+            }),
+            c_wrapper: self.c_wrapper.unwrap_or_else(|| {
+                WebRegWrapper::builder()
+                    .with_cookies("To be determined by the user's cookies.")
+                    .should_close_after_request(true)
+                    .try_build_wrapper()
+                    .unwrap()
+            }),
+            wrapper_settings: self.wrapper_settings.unwrap_or_default(),
+            cookie_generation: tokio::sync::RwLock::new(0),
+            api_base_endpoint: self.api_base_endpoint,
+            cookie_servers: self.cookie_servers,
+            current_cookie_server: AtomicUsize::new(0),
+            retention: self.retention,
+            max_body_size: self
+                .max_body_size
+                .unwrap_or(DEFAULT_MAX_BODY_SIZE_MB as usize * 1024 * 1024),
+            initial_login_attempts: self
+                .initial_login_attempts
+                .unwrap_or(MAX_NUM_LOGIN_FAILURES),
+            thresholds: self.thresholds,
+            allowed_origins: self.allowed_origins,
+            course_info_cache: self.course_info_cache.map(|cache| {
+                CourseInfoCache::new(Duration::from_secs(
+                    cache.ttl_secs.unwrap_or(DEFAULT_CACHE_TTL_SECS),
+                ))
+            }),
+            #[cfg(feature = "auth")]
+            auth_manager: self
+                .auth_manager
+                .unwrap_or_else(|| basicauth::AuthManager::new(":memory:")),
+            #[cfg(feature = "auth")]
+            rate_limiter: self.rate_limit.as_ref().map(RateLimiter::new),
+            #[cfg(feature = "postgres")]
+            postgres_url: self.postgres_url,
+            #[cfg(feature = "postgres")]
+            postgres: OnceLock::new(),
+        }
+    }
+}
+
+/// The settings that apply to every request made to WebReg.
+#[derive(Clone)]
+pub struct WrapperSettings {
+    /// The client that wrappers should use.
+    pub client: webweg_reqwest::Client,
+    /// The amount of time that a request can take.
+    pub timeout: Duration,
+    /// The user agent to make requests with, if not the wrapper's default.
+    pub user_agent: Option<String>,
+}
+
+impl Default for WrapperSettings {
+    fn default() -> Self {
+        Self {
+            client: Default::default(),
+            timeout: Duration::from_secs(DEFAULT_REQUEST_TIMEOUT_SECS),
+            user_agent: None,
+        }
+    }
+}
+
+impl WrapperSettings {
+    /// Creates a builder for a wrapper that uses these settings.
+    ///
+    /// # Returns
+    /// The builder.
+    pub fn wrapper_builder(&self) -> WebRegWrapperBuilder {
+        let builder = WebRegWrapper::builder()
+            .with_client(self.client.clone())
+            .with_default_timeout(self.timeout);
+        match self.user_agent {
+            Some(ref user_agent) => builder.with_user_agent(user_agent),
+            None => builder,
+        }
+    }
+}
+
+/// The limits that decide when the tracker gives up on a set of session cookies, and how
+/// long it waits before trying to get new ones.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct TrackerThresholds {
+    /// The number of consecutive failed requests allowed before new session cookies are
+    /// requested.
+    pub max_search_failures: usize,
+    /// The number of times new session cookies should be requested before the tracker
+    /// stops.
+    pub max_login_failures: i32,
+    /// The number of times the tracker should try to register new session cookies.
+    pub max_register_attempts: usize,
+    /// The base delay, in minutes, before requesting new session cookies. This grows
+    /// exponentially with each failure.
+    pub login_base_delay_mins: f64,
+}
+
+impl Default for TrackerThresholds {
+    fn default() -> Self {
+        Self {
+            max_search_failures: MAX_NUM_SEARCH_REQUESTS,
+            max_login_failures: MAX_NUM_LOGIN_FAILURES,
+            max_register_attempts: MAX_NUM_REGISTER,
+            login_base_delay_mins: BASE_DELAY_FOR_SESSION_COOKIE,
+        }
+    }
+}
+
 /// A structure that holds basic stats about the tracker's requests.
 #[derive(Default)]
 pub struct StatTracker {
@@ -135,6 +667,9 @@ pub struct StatTracker {
     pub num_requests: AtomicUsize,
     /// The total amount of time spent making those requests, in milliseconds.
     pub total_time_spent: AtomicUsize,
+    /// The number of failed requests, grouped by the category of failure (see
+    /// `error_category`).
+    pub error_counts: Mutex<HashMap<String, usize>>,
 }
 
 impl StatTracker {
@@ -153,6 +688,51 @@ impl StatTracker {
 
         recent_requests.push_back(time_of_req);
     }
+
+    /// Records a failed request.
+    ///
+    /// # Parameters
+    /// - `category`: The category of failure.
+    pub fn add_error(&self, category: impl Into<String>) {
+        *self
+            .error_counts
+            .lock()
+            .unwrap()
+            .entry(category.into())
+            .or_default() += 1;
+    }
+}
+
+/// The category that a failed request made while tracking is recorded under when a course
+/// was found, but it doesn't have any sections (which usually means that the session
+/// cookies are no longer valid).
+pub const EMPTY_RESPONSE_CATEGORY: &str = "empty_response";
+
+/// Gets the category that a failed request's error is recorded under.
+///
+/// # Parameters
+/// - `error`: The error.
+///
+/// # Returns
+/// The category. Errors from non-OK status codes are grouped by the status code (e.g.,
+/// `status_429`), and all other errors are grouped by their kind.
+pub fn error_category(error: &WrapperError) -> String {
+    match error {
+        WrapperError::RequestError(e) => match e.status() {
+            Some(status) => format!("status_{}", status.as_u16()),
+            None if e.is_timeout() => "timeout".to_owned(),
+            None => "request".to_owned(),
+        },
+        WrapperError::BadStatusCode(code, _) => format!("status_{code}"),
+        WrapperError::UrlParseError(_) => "url_parse".to_owned(),
+        WrapperError::InputError(..) => "input".to_owned(),
+        WrapperError::SerdeError(_) => "serde".to_owned(),
+        WrapperError::BadTimeError => "bad_time".to_owned(),
+        WrapperError::WebRegError(_) => "webreg".to_owned(),
+        WrapperError::SectionIdNotFound(..) => "section_not_found".to_owned(),
+        WrapperError::WrapperParsingError(_) => "parsing".to_owned(),
+        WrapperError::SessionNotValid => "session_not_valid".to_owned(),
+    }
 }
 
 /// A structure that holds information relating to the scraper and, more importantly, the
@@ -160,12 +740,222 @@ impl StatTracker {
 pub struct TermInfo {
     /// The term associated with this scraper.
     pub term: String,
+    /// The alias for this term, if any. This is used when naming output files.
+    pub alias: Option<String>,
     /// The cooldown, in seconds, between requests.
     pub cooldown: f64,
+    /// The adaptive cooldown settings, if the cooldown should be adapted.
+    pub adaptive_cooldown: Option<ConfigAdaptiveCooldown>,
+    /// The cooldown, in seconds, that is currently being used between requests. This is
+    /// only different from `cooldown` if the cooldown is adaptive.
+    pub current_cooldown: Mutex<f64>,
+    /// The maximum number of requests that can be in-flight at any time.
+    pub concurrency: usize,
     /// The courses to search for.
     pub search_query: Vec<SearchRequestBuilder>,
-    /// Tracker stats. This field contains information on the performance of the scraper.
-    pub tracker: StatTracker,
+    /// The IDs of the only sections to track. If this isn't empty, then these sections are
+    /// searched for instead of `search_query`.
+    pub watched_sections: Vec<String>,
+    /// Tracker stats. This field contains information on the performance of the scraper,
+    /// and is shared with the term's replacement when the configuration is reloaded.
+    pub tracker: Arc<StatTracker>,
+    /// Whether the scraped data should be saved to a file.
+    pub should_save: bool,
+    /// Whether the file that the scraped data is saved to should be compressed.
+    pub compress: bool,
+    /// Where the scraped data is saved to.
+    pub sink: OutputSink,
+    /// Whether a section's data should only be saved when it has changed.
+    pub only_on_change: bool,
+    /// The URL that should be notified when one of `notify_sections` opens a seat, if any.
+    pub notify_webhook: Option<String>,
+    /// The IDs of the sections that the webhook should be notified about.
+    pub notify_sections: Vec<String>,
+    /// The directory that output files are written to.
+    pub output_dir: PathBuf,
+    /// The name of the file, in the output directory, that the tracker is currently
+    /// writing to, if any.
+    pub current_output: Mutex<Option<String>>,
+    /// Whether the tracker for this term has been paused.
+    pub paused: AtomicBool,
+}
+
+impl TermInfo {
+    /// Creates the information for a term from its configuration.
+    ///
+    /// # Parameters
+    /// - `data`: The term's configuration.
+    /// - `output_dir`: The directory that output files are written to.
+    /// - `sink`: Where the scraped data is saved to.
+    ///
+    /// # Returns
+    /// The term information, with no stats.
+    pub fn from_config(data: ConfigTermDatum, output_dir: &Path, sink: OutputSink) -> Self {
+        Self {
+            term: data.term,
+            alias: data.alias,
+            cooldown: data.cooldown,
+            current_cooldown: Mutex::new(match data.adaptive_cooldown {
+                Some(ref settings) => data
+                    .cooldown
+                    .clamp(settings.min_cooldown, settings.max_cooldown),
+                None => data.cooldown,
+            }),
+            adaptive_cooldown: data.adaptive_cooldown,
+            concurrency: data.concurrency.unwrap_or(1).max(1),
+            search_query: data
+                .search_query
+                .into_iter()
+                .map(|query| {
+                    let mut parsed = SearchRequestBuilder::new();
+                    for level in query.levels {
+                        parsed = match level.as_str() {
+                            "g" => parsed.filter_courses_by(CourseLevelFilter::Graduate),
+                            "u" => parsed.filter_courses_by(CourseLevelFilter::UpperDivision),
+                            "l" => parsed.filter_courses_by(CourseLevelFilter::LowerDivision),
+                            _ => continue,
+                        };
+                    }
+
+                    for dept in query.departments {
+                        parsed = parsed.add_department(dept);
+                    }
+                    parsed
+                })
+                .collect(),
+            tracker: Default::default(),
+            should_save: data.save_data_to_file,
+            compress: data.compress.unwrap_or(false),
+            sink,
+            only_on_change: data.only_on_change.unwrap_or(false),
+            notify_webhook: data.notify_webhook,
+            notify_sections: data.notify_sections.unwrap_or_default(),
+            watched_sections: data.watched_sections.unwrap_or_default(),
+            output_dir: output_dir.to_path_buf(),
+            current_output: Default::default(),
+            paused: Default::default(),
+        }
+    }
+
+    /// Creates the information for a term that isn't saved anywhere and has no search
+    /// queries, for use in tests. Any field can be overridden with struct update syntax.
+    ///
+    /// # Parameters
+    /// - `term`: The term.
+    ///
+    /// # Returns
+    /// The term information.
+    #[cfg(test)]
+    pub fn for_test(term: &str) -> Self {
+        Self {
+            term: term.to_owned(),
+            alias: None,
+            cooldown: 1.5,
+            adaptive_cooldown: None,
+            current_cooldown: Mutex::new(1.5),
+            concurrency: 1,
+            search_query: vec![],
+            tracker: Default::default(),
+            should_save: false,
+            compress: false,
+            sink: Default::default(),
+            only_on_change: false,
+            notify_webhook: None,
+            notify_sections: vec![],
+            watched_sections: vec![],
+            output_dir: ".".into(),
+            current_output: Default::default(),
+            paused: Default::default(),
+        }
+    }
+
+    /// Gets the name that should be used when naming this term's output files.
+    ///
+    /// # Returns
+    /// The alias, if one was specified, or the term otherwise.
+    pub fn output_name(&self) -> &str {
+        self.alias.as_deref().unwrap_or(self.term.as_str())
+    }
+
+    /// Gets the path to the SQLite database that this term's data is saved to, if the
+    /// SQLite sink is used.
+    ///
+    /// # Returns
+    /// The path to the database.
+    pub fn sqlite_path(&self) -> PathBuf {
+        self.output_dir
+            .join(format!("enrollment_{}.db", self.output_name()))
+    }
+
+    /// Indicates whether the tracker for this term has been paused.
+    ///
+    /// # Returns
+    /// `true` if the tracker is paused, and `false` otherwise.
+    pub fn is_paused(&self) -> bool {
+        self.paused.load(Ordering::SeqCst)
+    }
+
+    /// Pauses or resumes the tracker for this term. A paused tracker makes no requests
+    /// until it's resumed.
+    ///
+    /// # Parameters
+    /// - `paused`: Whether the tracker should be paused.
+    pub fn set_paused(&self, paused: bool) {
+        self.paused.store(paused, Ordering::SeqCst);
+    }
+
+    /// Gets the cooldown that should currently be used between requests.
+    ///
+    /// # Returns
+    /// The cooldown, in seconds.
+    pub fn cooldown(&self) -> f64 {
+        *self.current_cooldown.lock().unwrap()
+    }
+
+    /// Adapts the cooldown based on how WebReg has been responding recently. If the
+    /// cooldown isn't adaptive, then the cooldown only backs off when requests fail.
+    ///
+    /// # Parameters
+    /// - `had_failure`: Whether any of the most recent requests failed.
+    pub fn adapt_cooldown(&self, had_failure: bool) {
+        let Some(ref settings) = self.adaptive_cooldown else {
+            let mut cooldown = self.current_cooldown.lock().unwrap();
+            *cooldown = backoff_cooldown(self.cooldown, *cooldown, had_failure);
+            return;
+        };
+
+        let avg_latency = {
+            let recent_requests = self.tracker.recent_requests.lock().unwrap();
+            let window = recent_requests.len().min(ADAPTIVE_COOLDOWN_WINDOW);
+            if window == 0 {
+                return;
+            }
+
+            recent_requests.iter().rev().take(window).sum::<usize>() / window
+        };
+
+        let mut cooldown = self.current_cooldown.lock().unwrap();
+        *cooldown = settings.next_cooldown(*cooldown, avg_latency, had_failure);
+    }
+}
+
+/// Computes the next cooldown for a term whose cooldown isn't adaptive. The cooldown is
+/// increased exponentially while requests fail, and decays back to the configured cooldown
+/// once they succeed.
+///
+/// # Parameters
+/// - `base`: The configured cooldown, in seconds.
+/// - `cooldown`: The current cooldown, in seconds.
+/// - `had_failure`: Whether any of the most recent requests failed.
+///
+/// # Returns
+/// The next cooldown, in seconds.
+fn backoff_cooldown(base: f64, cooldown: f64, had_failure: bool) -> f64 {
+    if had_failure {
+        (cooldown * FAILURE_BACKOFF_FACTOR).min(base * MAX_FAILURE_BACKOFF_MULTIPLIER)
+    } else {
+        (cooldown * FAILURE_BACKOFF_DECAY).max(base)
+    }
 }
 
 /// A structure that represents a configuration file specifically for the scraper. See the
@@ -181,16 +971,482 @@ pub struct ConfigScraper {
     pub api_base_endpoint: AddressPortInfo,
     /// The recovery address/port information. When the scraper is unable to get data
     /// for this particular term, it will attempt to request new session cookies for this
-    /// term so it can continue to get data.
-    pub cookie_server: AddressPortInfo,
+    /// term so it can continue to get data. If more than one cookie server is specified,
+    /// they're tried in order until one of them provides session cookies.
+    pub cookie_server: CookieServers,
     /// Information about what terms the scraper will be gathering data for.
     pub wrapper_data: Vec<ConfigTermDatum>,
     /// Whether the logging should be verbose or not.
     pub verbose: bool,
+    /// The retention policy for the tracker's output files. If this isn't specified, then
+    /// output files are kept indefinitely.
+    pub retention: Option<ConfigRetention>,
+    /// The directory that the tracker's output files are written to. This is created if it
+    /// doesn't exist. If this isn't specified, files are written to the current directory.
+    pub output_dir: Option<PathBuf>,
+    /// Where the tracker saves scraped data to. If this isn't specified, data is saved to
+    /// CSV files.
+    pub sink: Option<OutputSink>,
+    /// The maximum estimated number of requests per minute that the scraper is allowed to
+    /// make to WebReg across all terms. If the configuration is estimated to exceed this,
+    /// the scraper will refuse to start. If this isn't specified, there is no limit (but a
+    /// warning will still be logged if the estimate looks dangerous).
+    pub max_requests_per_minute: Option<f64>,
+    /// The maximum size of a response body that will be read, in megabytes. If this isn't
+    /// specified, this defaults to 32 megabytes.
+    pub max_body_size_mb: Option<u64>,
+    /// The number of times the initial login should be retried, 3 minutes apart, before
+    /// falling back to the usual exponential backoff. If this isn't specified, this defaults
+    /// to 30.
+    pub initial_login_attempts: Option<i32>,
+    /// The connection string for a PostgreSQL database that scraped data should be written
+    /// to, in addition to any CSV files. This requires the `postgres` feature.
+    pub postgres_url: Option<String>,
+    /// The user agent to use when making requests to WebReg. If this isn't specified, the
+    /// wrapper's default user agent is used.
+    pub user_agent: Option<String>,
+    /// The proxy that all requests to WebReg should go through, if any.
+    pub proxy: Option<String>,
+    /// How long a request to WebReg can take, in seconds, before it's considered to have
+    /// failed. If this isn't specified, this defaults to 30 seconds.
+    pub request_timeout_secs: Option<u64>,
+    /// The number of consecutive failed requests allowed before new session cookies are
+    /// requested. If this isn't specified, this defaults to 12.
+    pub max_search_failures: Option<usize>,
+    /// The number of times new session cookies should be requested, after being logged
+    /// out, before the tracker stops. If this isn't specified, this defaults to 30.
+    pub max_login_failures: Option<i32>,
+    /// The number of times the tracker should try to register new session cookies. If this
+    /// isn't specified, this defaults to 25.
+    pub max_register_attempts: Option<usize>,
+    /// The base delay, in minutes, before requesting new session cookies after being logged
+    /// out. If this isn't specified, this defaults to 10 minutes.
+    pub login_base_delay_mins: Option<f64>,
+    /// The origins that browsers are allowed to make cross-origin requests to the API from
+    /// (e.g., `https://example.com`, or `*` for any origin). If this isn't specified, no
+    /// CORS headers are sent, so only same-origin requests are allowed.
+    pub allowed_origins: Option<Vec<String>>,
+    /// How many requests can be made with each API key. If this isn't specified, requests
+    /// aren't rate-limited. This requires the `auth` feature.
+    pub rate_limit: Option<ConfigRateLimit>,
+    /// The path to the database holding the API keys, or `:memory:` for a temporary,
+    /// in-memory database. If this isn't specified, this defaults to `auth.db`. This
+    /// requires the `auth` feature.
+    pub auth_db: Option<String>,
+    /// How responses from the `course_info` endpoint should be cached. If this isn't
+    /// specified, every request is made to WebReg.
+    pub course_info_cache: Option<ConfigCache>,
+    /// How long, in seconds, to wait for the trackers to stop when shutting down before
+    /// exiting anyway. If this isn't specified, this defaults to 30 seconds.
+    pub shutdown_timeout_secs: Option<u64>,
 }
 
-/// A structure that represents an address and port.
+impl ConfigScraper {
+    /// Builds the HTTP client that should be used to make requests to WebReg.
+    ///
+    /// # Returns
+    /// The client, or an error if the proxy is invalid.
+    pub fn webreg_client(&self) -> webweg_reqwest::Result<webweg_reqwest::Client> {
+        let mut builder = webweg_reqwest::Client::builder();
+        if let Some(ref proxy) = self.proxy {
+            builder = builder.proxy(webweg_reqwest::Proxy::all(proxy)?);
+        }
+
+        builder.build()
+    }
+
+    /// Creates a builder for a wrapper that uses the given client, along with any other
+    /// settings in this configuration that apply to requests made to WebReg.
+    ///
+    /// # Parameters
+    /// - `client`: The client that the wrapper should use.
+    ///
+    /// # Returns
+    /// The builder.
+    pub fn wrapper_builder(&self, client: &webweg_reqwest::Client) -> WebRegWrapperBuilder {
+        self.wrapper_settings(client).wrapper_builder()
+    }
+
+    /// Gets the settings in this configuration that apply to requests made to WebReg.
+    ///
+    /// # Parameters
+    /// - `client`: The client that wrappers should use.
+    ///
+    /// # Returns
+    /// The settings.
+    pub fn wrapper_settings(&self, client: &webweg_reqwest::Client) -> WrapperSettings {
+        WrapperSettings {
+            client: client.clone(),
+            timeout: Duration::from_secs(
+                self.request_timeout_secs
+                    .unwrap_or(DEFAULT_REQUEST_TIMEOUT_SECS),
+            ),
+            user_agent: self.user_agent.clone(),
+        }
+    }
+
+    /// Gets the limits that decide when the tracker gives up on a set of session cookies.
+    ///
+    /// # Returns
+    /// The limits, with any that aren't specified set to their defaults.
+    pub fn thresholds(&self) -> TrackerThresholds {
+        let defaults = TrackerThresholds::default();
+        TrackerThresholds {
+            max_search_failures: self
+                .max_search_failures
+                .unwrap_or(defaults.max_search_failures),
+            max_login_failures: self
+                .max_login_failures
+                .unwrap_or(defaults.max_login_failures),
+            max_register_attempts: self
+                .max_register_attempts
+                .unwrap_or(defaults.max_register_attempts),
+            login_base_delay_mins: self
+                .login_base_delay_mins
+                .unwrap_or(defaults.login_base_delay_mins),
+        }
+    }
+
+    /// Gets the maximum size of a response body that will be read.
+    ///
+    /// # Returns
+    /// The maximum size, in bytes.
+    pub fn max_body_size(&self) -> usize {
+        let max_size_mb = self.max_body_size_mb.unwrap_or(DEFAULT_MAX_BODY_SIZE_MB);
+        usize::try_from(max_size_mb * 1024 * 1024).unwrap_or(usize::MAX)
+    }
+
+    /// Estimates the number of requests per minute that the scraper will make to WebReg
+    /// across all terms. Since every term waits `cooldown` seconds between the start of each
+    /// request, each term makes at most `60 / cooldown` requests per minute.
+    ///
+    /// # Returns
+    /// The estimated number of requests per minute.
+    pub fn estimate_requests_per_minute(&self) -> f64 {
+        self.wrapper_data
+            .iter()
+            .map(|data| {
+                let cooldown = data.min_cooldown();
+                if cooldown > 0.0 {
+                    60.0 / cooldown
+                } else {
+                    f64::INFINITY
+                }
+            })
+            .sum()
+    }
+
+    /// Checks that the request rate implied by this configuration isn't dangerous, logging
+    /// the estimated request rate and the estimated duration of each pass (i.e., the time it
+    /// takes to go through every course once) for each term.
+    ///
+    /// # Returns
+    /// `false` if the estimated request rate exceeds `max_requests_per_minute`, and `true`
+    /// otherwise.
+    pub fn check_request_rate(&self) -> bool {
+        for data in &self.wrapper_data {
+            // We can't know how many courses there are until we log in, so approximate. If
+            // only specific sections are tracked, then there's at most one course for each.
+            let num_courses = match data.watched_sections {
+                Some(ref ids) if !ids.is_empty() => ids.len(),
+                _ => data
+                    .search_query
+                    .iter()
+                    .map(|query| {
+                        if query.departments.is_empty() {
+                            APPROX_NUM_COURSES_ALL
+                        } else {
+                            query.departments.len() * APPROX_NUM_COURSES_PER_DEPT
+                        }
+                    })
+                    .sum::<usize>(),
+            };
+
+            info!(
+                "[{}] Estimated ~{num_courses} requests per pass (~{:.0} minutes per pass).",
+                data.term,
+                num_courses as f64 * data.min_cooldown() / 60.0
+            );
+        }
+
+        let estimate = self.estimate_requests_per_minute();
+        if let Some(max) = self.max_requests_per_minute {
+            if estimate > max {
+                error!(
+                    "The scraper is estimated to make {estimate:.1} requests per minute, which exceeds the configured maximum of {max:.1}. Increase the cooldown(s)."
+                );
+                return false;
+            }
+        }
+
+        if estimate > WARN_REQUESTS_PER_MINUTE {
+            warn!(
+                "The scraper is estimated to make {estimate:.1} requests per minute. This may get your account rate-limited or banned."
+            );
+        } else {
+            info!("The scraper is estimated to make {estimate:.1} requests per minute.");
+        }
+
+        true
+    }
+}
+
+/// A structure that represents how long the tracker's output files should be kept for.
 #[derive(Serialize, Deserialize, Clone)]
+#[serde(rename_all = "camelCase")]
+pub struct ConfigRetention {
+    /// The maximum age, in days, of an output file. Files whose last modification is older
+    /// than this will be deleted.
+    pub max_age_days: Option<u64>,
+    /// The maximum total size, in megabytes, of all output files for a term. When this is
+    /// exceeded, the oldest files for that term are deleted first.
+    pub max_total_size_mb: Option<u64>,
+}
+
+/// A structure that represents how responses should be cached.
+#[derive(Serialize, Deserialize, Clone, Copy, Debug, PartialEq)]
+#[serde(rename_all = "camelCase")]
+pub struct ConfigCache {
+    /// How long, in seconds, a response is served from the cache before it's requested
+    /// again. If this isn't specified, this defaults to 60 seconds.
+    pub ttl_secs: Option<u64>,
+}
+
+/// A cache whose entries expire a fixed amount of time after they're inserted.
+pub struct TtlCache<K, V> {
+    /// How long an entry is kept for.
+    ttl: Duration,
+    /// The entries, along with when they were inserted.
+    entries: Mutex<HashMap<K, (Instant, V)>>,
+}
+
+impl<K, V> TtlCache<K, V>
+where
+    K: Eq + Hash,
+    V: Clone,
+{
+    /// Creates an empty cache.
+    ///
+    /// # Parameters
+    /// - `ttl`: How long an entry is kept for.
+    ///
+    /// # Returns
+    /// The cache.
+    pub fn new(ttl: Duration) -> Self {
+        Self {
+            ttl,
+            entries: Default::default(),
+        }
+    }
+
+    /// Gets an entry from the cache, if it hasn't expired.
+    ///
+    /// # Parameters
+    /// - `key`: The key.
+    /// - `now`: The current time.
+    ///
+    /// # Returns
+    /// The entry, or `None` if there's no entry or it has expired.
+    pub fn get(&self, key: &K, now: Instant) -> Option<V> {
+        let entries = self.entries.lock().unwrap();
+        let (inserted, value) = entries.get(key)?;
+        (now.saturating_duration_since(*inserted) < self.ttl).then(|| value.clone())
+    }
+
+    /// Inserts an entry into the cache, removing any entries that have expired.
+    ///
+    /// # Parameters
+    /// - `key`: The key.
+    /// - `value`: The value.
+    /// - `now`: The current time.
+    pub fn insert(&self, key: K, value: V, now: Instant) {
+        let mut entries = self.entries.lock().unwrap();
+        entries.retain(|_, (inserted, _)| now.saturating_duration_since(*inserted) < self.ttl);
+        entries.insert(key, (now, value));
+    }
+
+    /// Gets an entry from the cache, or fetches and caches it if there's no entry or it
+    /// has expired. Errors aren't cached.
+    ///
+    /// # Parameters
+    /// - `key`: The key.
+    /// - `fetch`: The function that fetches the value.
+    ///
+    /// # Returns
+    /// The value, or the error that occurred when fetching it.
+    pub async fn get_or_fetch<E, F, Fut>(&self, key: K, fetch: F) -> Result<V, E>
+    where
+        F: FnOnce() -> Fut,
+        Fut: Future<Output = Result<V, E>>,
+    {
+        if let Some(value) = self.get(&key, Instant::now()) {
+            return Ok(value);
+        }
+
+        let value = fetch().await?;
+        self.insert(key, value.clone(), Instant::now());
+        Ok(value)
+    }
+}
+
+/// The term, subject code, and course number that a course's information is cached under.
+pub type CourseKey = (String, String, String);
+
+/// The caches for the `course_info` endpoint. Raw and parsed responses are cached
+/// separately.
+pub struct CourseInfoCache {
+    /// The raw responses.
+    pub raw: TtlCache<CourseKey, String>,
+    /// The parsed responses.
+    pub parsed: TtlCache<CourseKey, Vec<CourseSection>>,
+}
+
+impl CourseInfoCache {
+    /// Creates empty caches.
+    ///
+    /// # Parameters
+    /// - `ttl`: How long a response is cached for.
+    ///
+    /// # Returns
+    /// The caches.
+    pub fn new(ttl: Duration) -> Self {
+        Self {
+            raw: TtlCache::new(ttl),
+            parsed: TtlCache::new(ttl),
+        }
+    }
+
+    /// Gets the key that a course's information is cached under.
+    ///
+    /// # Parameters
+    /// - `term`: The term.
+    /// - `subject`: The subject code.
+    /// - `number`: The course number.
+    ///
+    /// # Returns
+    /// The key, which doesn't depend on case or surrounding whitespace.
+    pub fn key(term: &str, subject: &str, number: &str) -> CourseKey {
+        (
+            term.trim().to_uppercase(),
+            subject.trim().to_uppercase(),
+            number.trim().to_uppercase(),
+        )
+    }
+}
+
+/// A structure that represents how many requests can be made with each API key.
+#[derive(Serialize, Deserialize, Clone, Copy, Debug, PartialEq)]
+#[serde(rename_all = "camelCase")]
+pub struct ConfigRateLimit {
+    /// The number of requests that can be made per minute, on average.
+    pub requests_per_minute: f64,
+    /// The number of requests that can be made at once, after no requests have been made
+    /// for a while. If this isn't specified, this defaults to one minute's worth of
+    /// requests.
+    pub burst: Option<u32>,
+}
+
+/// A token bucket rate limiter, with one bucket for each key. Each bucket starts full and
+/// gains tokens at a steady rate, up to its capacity; every request takes one token.
+#[cfg(feature = "auth")]
+pub struct RateLimiter {
+    /// The maximum number of tokens in a bucket.
+    capacity: f64,
+    /// The number of tokens added to a bucket every second.
+    refill_per_sec: f64,
+    /// The number of tokens in each key's bucket, as of the given time.
+    buckets: Mutex<HashMap<String, (f64, Instant)>>,
+}
+
+#[cfg(feature = "auth")]
+impl RateLimiter {
+    /// Creates a rate limiter.
+    ///
+    /// # Parameters
+    /// - `config`: The rate limit.
+    ///
+    /// # Returns
+    /// The rate limiter.
+    pub fn new(config: &ConfigRateLimit) -> Self {
+        let refill_per_sec = config.requests_per_minute.max(f64::MIN_POSITIVE) / 60.0;
+        let capacity = match config.burst {
+            Some(burst) => burst as f64,
+            None => config.requests_per_minute.floor(),
+        };
+
+        Self {
+            capacity: capacity.max(1.0),
+            refill_per_sec,
+            buckets: Default::default(),
+        }
+    }
+
+    /// Takes a token from the given key's bucket, if there is one.
+    ///
+    /// # Parameters
+    /// - `key`: The key.
+    /// - `now`: The current time.
+    ///
+    /// # Returns
+    /// Nothing if a token was taken, or how long to wait until the next token is available
+    /// otherwise.
+    pub fn try_acquire(&self, key: &str, now: Instant) -> Result<(), Duration> {
+        let mut buckets = self.buckets.lock().unwrap();
+        let (tokens, last_refill) = buckets
+            .entry(key.to_owned())
+            .or_insert((self.capacity, now));
+        let elapsed = now.saturating_duration_since(*last_refill).as_secs_f64();
+        *tokens = (*tokens + elapsed * self.refill_per_sec).min(self.capacity);
+        *last_refill = now;
+
+        if *tokens >= 1.0 {
+            *tokens -= 1.0;
+            Ok(())
+        } else {
+            Err(
+                Duration::try_from_secs_f64((1.0 - *tokens) / self.refill_per_sec)
+                    .unwrap_or(Duration::MAX),
+            )
+        }
+    }
+}
+
+/// One or more cookie servers.
+#[derive(Serialize, Deserialize, Clone)]
+#[serde(untagged)]
+pub enum CookieServers {
+    /// A single cookie server.
+    One(AddressPortInfo),
+    /// Several cookie servers, in the order that they should be tried.
+    Many(Vec<AddressPortInfo>),
+}
+
+impl CookieServers {
+    /// Gets the cookie servers.
+    ///
+    /// # Returns
+    /// The cookie servers, in the order that they should be tried.
+    pub fn as_slice(&self) -> &[AddressPortInfo] {
+        match self {
+            CookieServers::One(server) => std::slice::from_ref(server),
+            CookieServers::Many(servers) => servers,
+        }
+    }
+
+    /// Converts this into the list of cookie servers.
+    ///
+    /// # Returns
+    /// The cookie servers, in the order that they should be tried.
+    pub fn into_vec(self) -> Vec<AddressPortInfo> {
+        match self {
+            CookieServers::One(server) => vec![server],
+            CookieServers::Many(servers) => servers,
+        }
+    }
+}
+
+/// A structure that represents an address and port.
+#[derive(Serialize, Deserialize, Clone, Default)]
 pub struct AddressPortInfo {
     /// The address.
     pub address: String,
@@ -199,7 +1455,7 @@ pub struct AddressPortInfo {
 }
 
 /// A structure that represents a specific term that the scraper should consider.
-#[derive(Serialize, Deserialize)]
+#[derive(Serialize, Deserialize, Clone)]
 #[serde(rename_all = "camelCase")]
 pub struct ConfigTermDatum {
     /// The term, represented by four characters. The first two characters must be
@@ -213,17 +1469,110 @@ pub struct ConfigTermDatum {
     /// The last two characters must represent the year associated with that term.
     /// For example, `FA22` represents the Fall 2022 term, and `S120` represents the
     /// Summer 1 2020 term.
+    ///
+    /// Alternatively, this can be `current`, in which case this entry is expanded at
+    /// startup into one entry for each term that is currently available on WebReg.
     pub term: String,
+    /// An alias for this term, if any. When specified, this is used in place of the term
+    /// when naming output files (e.g., `summer2024` instead of `S124`).
+    pub alias: Option<String>,
     /// The delay between each individual request for a course, in seconds.
     pub cooldown: f64,
+    /// Settings for adapting the cooldown to WebReg's responsiveness. If this isn't
+    /// specified, the cooldown is fixed.
+    pub adaptive_cooldown: Option<ConfigAdaptiveCooldown>,
+    /// The maximum number of requests for course data that can be in-flight at any time.
+    /// Regardless of this value, requests will be started at least `cooldown` seconds
+    /// apart. By default, this is `1`, i.e., requests are made one at a time.
+    pub concurrency: Option<usize>,
     /// The courses that the scraper should be gathering data for.
     pub search_query: Vec<ConfigSearchQuery>,
     /// Whether we should be saving data scraped for this term to a file.
     pub save_data_to_file: bool,
+    /// Whether the file that data is saved to should be compressed with gzip. By default,
+    /// this is `false`.
+    pub compress: Option<bool>,
+    /// Whether a section's data should only be saved when its seat counts have changed
+    /// since they were last saved. By default, this is `false`.
+    pub only_on_change: Option<bool>,
+    /// The URL that should be notified, via a `POST` request, when one of `notify_sections`
+    /// goes from having no available seats to having at least one.
+    pub notify_webhook: Option<String>,
+    /// The IDs of the sections that the webhook should be notified about.
+    pub notify_sections: Option<Vec<String>>,
+    /// The IDs of the only sections that should be tracked. If this is specified, then
+    /// `search_query` is ignored, and only the courses containing these sections are
+    /// requested.
+    pub watched_sections: Option<Vec<String>>,
+}
+
+impl ConfigTermDatum {
+    /// Gets the smallest cooldown that the scraper may use for this term.
+    ///
+    /// # Returns
+    /// The minimum adaptive cooldown if the cooldown is adaptive, or the cooldown
+    /// otherwise.
+    pub fn min_cooldown(&self) -> f64 {
+        match self.adaptive_cooldown {
+            Some(ref settings) => settings.min_cooldown,
+            None => self.cooldown,
+        }
+    }
+}
+
+/// A structure that represents how the cooldown for a term should adapt to WebReg's
+/// responsiveness.
+#[derive(Serialize, Deserialize, Clone)]
+#[serde(rename_all = "camelCase")]
+pub struct ConfigAdaptiveCooldown {
+    /// The smallest cooldown that can be used, in seconds.
+    pub min_cooldown: f64,
+    /// The largest cooldown that can be used, in seconds.
+    pub max_cooldown: f64,
+    /// The average request time, in milliseconds, above which WebReg is considered to be
+    /// under load. By default, this is 2000 milliseconds.
+    pub latency_threshold_ms: Option<usize>,
+}
+
+impl ConfigAdaptiveCooldown {
+    /// Computes the next cooldown. The cooldown is increased if a request failed or if
+    /// requests are slow, and decreased otherwise.
+    ///
+    /// # Parameters
+    /// - `cooldown`: The current cooldown, in seconds.
+    /// - `avg_latency`: The average time of the most recent requests, in milliseconds.
+    /// - `had_failure`: Whether any of the most recent requests failed.
+    ///
+    /// # Returns
+    /// The next cooldown, in seconds, which is always between the minimum and maximum.
+    pub fn next_cooldown(&self, cooldown: f64, avg_latency: usize, had_failure: bool) -> f64 {
+        let threshold = self
+            .latency_threshold_ms
+            .unwrap_or(DEFAULT_LATENCY_THRESHOLD_MS);
+        let next = if had_failure || avg_latency > threshold {
+            cooldown * ADAPTIVE_COOLDOWN_INCREASE
+        } else {
+            cooldown * ADAPTIVE_COOLDOWN_DECREASE
+        };
+
+        next.clamp(self.min_cooldown, self.max_cooldown)
+    }
+}
+
+/// Where the tracker saves scraped data to.
+#[derive(Serialize, Deserialize, Clone, Copy, Default, PartialEq, Eq, Debug)]
+#[serde(rename_all = "lowercase")]
+pub enum OutputSink {
+    /// Data is saved to a CSV file, with one file per day.
+    #[default]
+    Csv,
+    /// Data is saved to the `enrollment` table of a SQLite database, with one database per
+    /// term.
+    Sqlite,
 }
 
 /// A structure that represents a search query for a term for the scraper.
-#[derive(Serialize, Deserialize)]
+#[derive(Serialize, Deserialize, Clone)]
 pub struct ConfigSearchQuery {
     /// The course levels to consider. Three levels are currently recognized:
     /// - `g`: graduate courses
@@ -234,3 +1583,330 @@ pub struct ConfigSearchQuery {
     /// specified, then all courses will be fetched.
     pub departments: Vec<String>,
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn make_settings() -> ConfigAdaptiveCooldown {
+        ConfigAdaptiveCooldown {
+            min_cooldown: 1.0,
+            max_cooldown: 4.0,
+            latency_threshold_ms: Some(1000),
+        }
+    }
+
+    #[test]
+    fn test_next_cooldown_backs_off() {
+        let settings = make_settings();
+        assert_eq!(3.0, settings.next_cooldown(2.0, 1500, false));
+        assert_eq!(3.0, settings.next_cooldown(2.0, 200, true));
+        assert_eq!(4.0, settings.next_cooldown(3.0, 1500, false));
+    }
+
+    #[test]
+    fn test_next_cooldown_recovers() {
+        let settings = make_settings();
+        assert_eq!(1.8, settings.next_cooldown(2.0, 200, false));
+        assert_eq!(1.0, settings.next_cooldown(1.05, 200, false));
+    }
+
+    #[test]
+    fn test_backoff_cooldown() {
+        let mut cooldown = 1.0;
+        for expected in [2.0, 4.0, 8.0, 8.0] {
+            cooldown = backoff_cooldown(1.0, cooldown, true);
+            assert_eq!(expected, cooldown);
+        }
+
+        for expected in [4.0, 2.0, 1.0, 1.0] {
+            cooldown = backoff_cooldown(1.0, cooldown, false);
+            assert_eq!(expected, cooldown);
+        }
+    }
+
+    fn make_config(extra: serde_json::Value) -> ConfigScraper {
+        let mut config = serde_json::json!({
+            "configName": "test",
+            "apiBaseEndpoint": { "address": "127.0.0.1", "port": 3000 },
+            "cookieServer": { "address": "127.0.0.1", "port": 3001 },
+            "wrapperData": [],
+            "verbose": false
+        });
+        config
+            .as_object_mut()
+            .unwrap()
+            .extend(extra.as_object().unwrap().clone());
+        serde_json::from_value(config).unwrap()
+    }
+
+    #[tokio::test]
+    async fn test_ttl_cache() {
+        let cache = TtlCache::new(Duration::from_millis(100));
+        let calls = AtomicUsize::new(0);
+        let fetch = || async {
+            calls.fetch_add(1, Ordering::SeqCst);
+            Ok::<_, ()>(vec![1, 2, 3])
+        };
+
+        let key = CourseInfoCache::key("fa24", "cse ", "100");
+        assert_eq!(
+            Ok(vec![1, 2, 3]),
+            cache.get_or_fetch(key.clone(), fetch).await
+        );
+        // A second request within the TTL is served from the cache, even if the course is
+        // written differently.
+        let same_key = CourseInfoCache::key("FA24", "CSE", "100");
+        assert_eq!(Ok(vec![1, 2, 3]), cache.get_or_fetch(same_key, fetch).await);
+        assert_eq!(1, calls.load(Ordering::SeqCst));
+
+        // Errors aren't cached.
+        let other_key = CourseInfoCache::key("FA24", "CSE", "101");
+        assert_eq!(
+            Err(()),
+            cache
+                .get_or_fetch(other_key.clone(), || async { Err::<Vec<i32>, _>(()) })
+                .await
+        );
+        assert_eq!(
+            Ok(vec![1, 2, 3]),
+            cache.get_or_fetch(other_key, fetch).await
+        );
+        assert_eq!(2, calls.load(Ordering::SeqCst));
+
+        // Once the entry expires, it's fetched again.
+        tokio::time::sleep(Duration::from_millis(150)).await;
+        assert_eq!(Ok(vec![1, 2, 3]), cache.get_or_fetch(key, fetch).await);
+        assert_eq!(3, calls.load(Ordering::SeqCst));
+    }
+
+    #[test]
+    fn test_ttl_cache_expiry() {
+        let cache = TtlCache::new(Duration::from_secs(60));
+        let start = Instant::now();
+        cache.insert("a", 1, start);
+        assert_eq!(Some(1), cache.get(&"a", start + Duration::from_secs(59)));
+        assert_eq!(None, cache.get(&"a", start + Duration::from_secs(60)));
+
+        // Expired entries are removed when another entry is inserted.
+        cache.insert("b", 2, start + Duration::from_secs(61));
+        assert_eq!(1, cache.entries.lock().unwrap().len());
+    }
+
+    #[test]
+    fn test_error_counts() {
+        let tracker = StatTracker::default();
+        let errors = [
+            WrapperError::BadStatusCode(429, None),
+            WrapperError::SessionNotValid,
+            WrapperError::BadStatusCode(429, Some("Too Many Requests".to_owned())),
+            WrapperError::BadStatusCode(500, None),
+            WrapperError::SerdeError(serde_json::from_str::<serde_json::Value>("<").unwrap_err()),
+            WrapperError::SessionNotValid,
+            WrapperError::BadStatusCode(429, None),
+        ];
+        for error in &errors {
+            tracker.add_error(error_category(error));
+        }
+        tracker.add_error(EMPTY_RESPONSE_CATEGORY);
+
+        let expected = HashMap::from([
+            ("status_429".to_owned(), 3),
+            ("status_500".to_owned(), 1),
+            ("session_not_valid".to_owned(), 2),
+            ("serde".to_owned(), 1),
+            (EMPTY_RESPONSE_CATEGORY.to_owned(), 1),
+        ]);
+        assert_eq!(expected, *tracker.error_counts.lock().unwrap());
+        // Errors shouldn't count as requests on their own.
+        assert_eq!(0, tracker.num_requests.load(Ordering::SeqCst));
+    }
+
+    #[test]
+    fn test_cookie_servers() {
+        let config = make_config(serde_json::json!({}));
+        assert_eq!(1, config.cookie_server.as_slice().len());
+
+        let config = make_config(serde_json::json!({
+            "cookieServer": [
+                { "address": "127.0.0.1", "port": 3001 },
+                { "address": "10.0.0.2", "port": 3002 }
+            ]
+        }));
+        let state = WrapperState::builder()
+            .with_cookie_servers(config.cookie_server.into_vec())
+            .build();
+        assert_eq!(2, state.cookie_servers.len());
+        assert_eq!(3001, state.cookie_server().unwrap().port);
+
+        state.current_cookie_server.store(1, Ordering::SeqCst);
+        assert_eq!(3002, state.cookie_server().unwrap().port);
+    }
+
+    #[test]
+    fn test_thresholds() {
+        let config = make_config(serde_json::json!({}));
+        assert_eq!(TrackerThresholds::default(), config.thresholds());
+
+        let config = make_config(serde_json::json!({
+            "maxSearchFailures": 3,
+            "maxLoginFailures": 5,
+            "maxRegisterAttempts": 2,
+            "loginBaseDelayMins": 0.5,
+            // Otherwise, creating the state would create `auth.db`.
+            "authDb": ":memory:"
+        }));
+        let expected = TrackerThresholds {
+            max_search_failures: 3,
+            max_login_failures: 5,
+            max_register_attempts: 2,
+            login_base_delay_mins: 0.5,
+        };
+        assert_eq!(expected, config.thresholds());
+
+        let client = config.webreg_client().unwrap();
+        let state = WrapperState::new(config, client);
+        assert_eq!(expected, state.thresholds);
+    }
+
+    fn make_term(term: &str, cooldown: f64) -> serde_json::Value {
+        serde_json::json!({
+            "term": term,
+            "cooldown": cooldown,
+            "searchQuery": [],
+            "saveDataToFile": false
+        })
+    }
+
+    #[test]
+    fn test_watched_and_notify_sections() {
+        let mut term = make_term("FA24", 2.0);
+        term["watchedSections"] = serde_json::json!(["123456", "654321"]);
+        term["notifySections"] = serde_json::json!(["123456"]);
+        let config = make_config(serde_json::json!({ "wrapperData": [term] }));
+        let data = &config.wrapper_data[0];
+        assert_eq!(
+            Some(vec!["123456".to_owned(), "654321".to_owned()]),
+            data.watched_sections
+        );
+        assert_eq!(Some(vec!["123456".to_owned()]), data.notify_sections);
+    }
+
+    #[test]
+    fn test_estimate_requests_per_minute() {
+        let config = make_config(serde_json::json!({}));
+        assert_eq!(0.0, config.estimate_requests_per_minute());
+
+        // Each term makes `60 / cooldown` requests per minute.
+        let config = make_config(serde_json::json!({
+            "wrapperData": [make_term("FA24", 2.0), make_term("WI25", 3.0)]
+        }));
+        assert_eq!(50.0, config.estimate_requests_per_minute());
+
+        // With an adaptive cooldown, the scraper may go as fast as the minimum cooldown.
+        let mut adaptive = make_term("SP25", 10.0);
+        adaptive["adaptiveCooldown"] = serde_json::json!({
+            "minCooldown": 1.0,
+            "maxCooldown": 20.0
+        });
+        let config = make_config(serde_json::json!({
+            "wrapperData": [make_term("FA24", 2.0), adaptive]
+        }));
+        assert_eq!(90.0, config.estimate_requests_per_minute());
+    }
+
+    #[test]
+    fn test_check_request_rate() {
+        // Without a maximum, any rate is allowed.
+        let config = make_config(serde_json::json!({
+            "wrapperData": [make_term("FA24", 0.5)]
+        }));
+        assert!(config.check_request_rate());
+
+        let config = make_config(serde_json::json!({
+            "wrapperData": [make_term("FA24", 2.0), make_term("WI25", 3.0)],
+            "maxRequestsPerMinute": 50.0
+        }));
+        assert!(config.check_request_rate());
+
+        let config = make_config(serde_json::json!({
+            "wrapperData": [make_term("FA24", 2.0), make_term("WI25", 3.0)],
+            "maxRequestsPerMinute": 49.0
+        }));
+        assert!(!config.check_request_rate());
+    }
+
+    #[test]
+    fn test_zero_cooldown_request_rate() {
+        let config = make_config(serde_json::json!({
+            "wrapperData": [make_term("FA24", 0.0)],
+            "maxRequestsPerMinute": 1000.0
+        }));
+        assert_eq!(f64::INFINITY, config.estimate_requests_per_minute());
+        assert!(!config.check_request_rate());
+    }
+
+    #[test]
+    fn test_invalid_proxy() {
+        let config = make_config(serde_json::json!({ "proxy": "not a proxy" }));
+        assert!(config.webreg_client().is_err());
+    }
+
+    #[tokio::test]
+    async fn test_request_timeout() {
+        let listener = tokio::net::TcpListener::bind("127.0.0.1:0").await.unwrap();
+        let proxy = format!("http://{}", listener.local_addr().unwrap());
+        let config = make_config(serde_json::json!({
+            "proxy": proxy,
+            "requestTimeoutSecs": 1
+        }));
+        let wrapper = config
+            .wrapper_builder(&config.webreg_client().unwrap())
+            .with_cookies("abc")
+            .try_build_wrapper()
+            .unwrap();
+
+        // The proxy accepts the connection but never responds.
+        let _server = tokio::spawn(async move {
+            let (stream, _) = listener.accept().await.unwrap();
+            tokio::time::sleep(Duration::from_secs(60)).await;
+            drop(stream);
+        });
+
+        let start = Instant::now();
+        assert!(wrapper.get_all_terms().await.is_err());
+        assert!(start.elapsed() < Duration::from_secs(5));
+    }
+
+    #[tokio::test]
+    async fn test_wrapper_uses_proxy() {
+        use tokio::io::AsyncReadExt;
+
+        let listener = tokio::net::TcpListener::bind("127.0.0.1:0").await.unwrap();
+        let proxy = format!("http://{}", listener.local_addr().unwrap());
+        let config = make_config(serde_json::json!({
+            "proxy": proxy,
+            "userAgent": "webreg_scraper test"
+        }));
+        let wrapper = config
+            .wrapper_builder(&config.webreg_client().unwrap())
+            .with_cookies("abc")
+            .try_build_wrapper()
+            .unwrap();
+
+        let received = tokio::spawn(async move {
+            let (mut stream, _) = listener.accept().await.unwrap();
+            let mut buf = vec![0; 1024];
+            let n = stream.read(&mut buf).await.unwrap();
+            String::from_utf8_lossy(&buf[..n]).into_owned()
+        });
+
+        // The proxy closes the connection without responding, so the request fails; all
+        // that matters is that it went through the proxy.
+        assert!(wrapper.get_all_terms().await.is_err());
+        assert!(received
+            .await
+            .unwrap()
+            .starts_with("CONNECT act.ucsd.edu:443"));
+    }
+}
@@ -0,0 +1,2 @@
+ALTER TABLE `api_tokens`
+ADD COLUMN `scope` VARCHAR(16) NOT NULL DEFAULT 'write'
\ No newline at end of file
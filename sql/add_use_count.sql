@@ -0,0 +1,2 @@
+ALTER TABLE `api_tokens`
+ADD COLUMN `use_count` INTEGER NOT NULL DEFAULT 0
\ No newline at end of file
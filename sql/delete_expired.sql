@@ -0,0 +1,3 @@
+DELETE
+FROM `api_tokens`
+WHERE `expires_at` < ?1
\ No newline at end of file
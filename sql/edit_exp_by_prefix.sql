@@ -0,0 +1,5 @@
+UPDATE `api_tokens`
+SET
+    expires_at = ?1
+WHERE
+    prefix = ?2
\ No newline at end of file
@@ -0,0 +1,5 @@
+UPDATE `api_tokens`
+SET
+    token = ?1
+WHERE
+    token = ?2
\ No newline at end of file
@@ -1,4 +0,0 @@
-SELECT *
-FROM `api_tokens`
-WHERE `prefix` = ?1
-  AND `token` = ?2
\ No newline at end of file
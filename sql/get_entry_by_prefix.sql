@@ -0,0 +1,3 @@
+SELECT *
+FROM `api_tokens`
+WHERE `prefix` = ?1
\ No newline at end of file
@@ -0,0 +1,3 @@
+SELECT *
+FROM `api_tokens`
+WHERE `token` NOT LIKE 'sha256$%'
\ No newline at end of file
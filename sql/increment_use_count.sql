@@ -0,0 +1,5 @@
+UPDATE `api_tokens`
+SET
+    use_count = use_count + 1
+WHERE
+    prefix = ?1
\ No newline at end of file
@@ -3,5 +3,7 @@ CREATE TABLE IF NOT EXISTS `api_tokens` (
     `token` VARCHAR(255) NOT NULL PRIMARY KEY UNIQUE,
     `created_at` DATETIME NOT NULL,
     `expires_at` DATETIME NOT NULL,
-    `description` TEXT
+    `description` TEXT,
+    `use_count` INTEGER NOT NULL DEFAULT 0,
+    `scope` VARCHAR(16) NOT NULL DEFAULT 'write'
 )
\ No newline at end of file
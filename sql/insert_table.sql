@@ -1,2 +1,2 @@
-INSERT INTO `api_tokens` (prefix, token, created_at, expires_at, description)
-VALUES (?1, ?2, ?3, ?4, ?5)
\ No newline at end of file
+INSERT INTO `api_tokens` (prefix, token, created_at, expires_at, description, scope)
+VALUES (?1, ?2, ?3, ?4, ?5, ?6)
\ No newline at end of file